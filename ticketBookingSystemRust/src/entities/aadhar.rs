@@ -0,0 +1,161 @@
+// Imports Serde traits; an AadharNumber is stored as a plain JSON string.
+use serde::{Deserialize, Serialize};
+// Imports fmt for the masked Display and Debug output and the error messages.
+use std::fmt;
+
+// Number of digits in an Aadhar number.
+pub const AADHAR_DIGITS: usize = 12;
+// Number of trailing digits left visible when masked.
+const VISIBLE_DIGITS: usize = 4;
+// Prefix of values encrypted at rest by service::encryption: "enc:v1:<key id>:<base64 of nonce +
+// ciphertext>".
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+// Verhoeff multiplication table (the dihedral group D5).
+const VERHOEFF_D: [[u8; 10]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+    [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+    [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+    [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+    [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+    [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+    [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+    [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+    [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+// Verhoeff permutation table, applied once per digit position (repeating every 8 positions).
+const VERHOEFF_P: [[u8; 10]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+    [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+    [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+    [9, 4, 5, 3, 1, 2, 8, 7, 6, 0],
+    [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+    [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+    [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+];
+
+// Why a value is not a valid Aadhar number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AadharError {
+    // The value holds something other than digits (spaces and hyphens between groups are allowed).
+    NotDigits,
+    // The value does not have exactly 12 digits; holds the number found.
+    WrongLength(usize),
+    // Aadhar numbers never start with 0 or 1.
+    InvalidFirstDigit,
+    // The last digit is not the Verhoeff check digit of the first eleven.
+    ChecksumMismatch,
+    // The value is encrypted and cannot be checked without the key.
+    Encrypted,
+}
+
+impl fmt::Display for AadharError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AadharError::NotDigits => write!(f, "must contain only digits"),
+            AadharError::WrongLength(n) => write!(f, "must have {} digits, found {}", AADHAR_DIGITS, n),
+            AadharError::InvalidFirstDigit => write!(f, "cannot start with 0 or 1"),
+            AadharError::ChecksumMismatch => write!(f, "check digit does not match"),
+            AadharError::Encrypted => write!(f, "is encrypted; configure the key to check it"),
+        }
+    }
+}
+
+impl std::error::Error for AadharError {}
+
+// An Aadhar number as stored with a passenger. Values typed in are checked by parse; values read
+// from storage are accepted as they are (older records may be invalid, and encrypted ones cannot be
+// checked without the key), so they can be flagged with validate instead of failing to load.
+// Display and Debug mask all but the last 4 digits; reveal is the one way to the full value.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct AadharNumber(String);
+
+impl AadharNumber {
+    // Parses user input, accepting "234123412346" as well as "2341 2341 2346" or "2341-2341-2346",
+    // and stores the 12 digits without separators.
+    pub fn parse(input: &str) -> Result<Self, AadharError> {
+        let digits: String = input.trim().chars().filter(|c| *c != ' ' && *c != '-').collect();
+        let aadhar = AadharNumber(digits);
+        aadhar.validate()?;
+        Ok(aadhar)
+    }
+
+    // Wraps a value read from storage without checking it, like deserializing does; values from
+    // anywhere else go through parse.
+    pub(crate) fn from_stored(value: impl Into<String>) -> Self {
+        AadharNumber(value.into())
+    }
+
+    // Checks the length, first digit and Verhoeff check digit.
+    pub fn validate(&self) -> Result<(), AadharError> {
+        if self.is_encrypted() {
+            return Err(AadharError::Encrypted);
+        }
+        if !self.0.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AadharError::NotDigits);
+        }
+        if self.0.len() != AADHAR_DIGITS {
+            return Err(AadharError::WrongLength(self.0.len()));
+        }
+        if self.0.starts_with(['0', '1']) {
+            return Err(AadharError::InvalidFirstDigit);
+        }
+        if !verhoeff_valid(&self.0) {
+            return Err(AadharError::ChecksumMismatch);
+        }
+        Ok(())
+    }
+
+    // Returns true if the value is a valid Aadhar number.
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    // Returns true if the value is encrypted at rest and was read without the key.
+    pub fn is_encrypted(&self) -> bool {
+        self.0.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    // Returns the value with all but the last 4 digits hidden, e.g. "XXXX XXXX 2346". Encrypted
+    // values and values too short to mask are hidden entirely.
+    pub fn masked(&self) -> String {
+        let digits: Vec<char> = self.0.chars().filter(|c| c.is_ascii_digit()).collect();
+        if self.is_encrypted() || digits.len() <= VISIBLE_DIGITS {
+            return "XXXX XXXX XXXX".to_string();
+        }
+        let last: String = digits[digits.len() - VISIBLE_DIGITS..].iter().collect();
+        format!("XXXX XXXX {}", last)
+    }
+
+    // Access to the full stored value, for staff checking documents and for the storage
+    // layer. Everything printed goes through Display or Debug, which mask it.
+    pub fn reveal(&self) -> &str {
+        &self.0
+    }
+}
+
+// Renders the masked value.
+impl fmt::Display for AadharNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.masked())
+    }
+}
+
+// Implements Debug with the value masked, so {:?} in logs and panics cannot leak it.
+impl fmt::Debug for AadharNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AadharNumber({})", self.masked())
+    }
+}
+
+// Returns true if the last digit of `digits` is the Verhoeff check digit of the others.
+fn verhoeff_valid(digits: &str) -> bool {
+    let mut check = 0u8;
+    for (i, digit) in digits.bytes().rev().enumerate() {
+        check = VERHOEFF_D[check as usize][VERHOEFF_P[i % 8][(digit - b'0') as usize] as usize];
+    }
+    check == 0
+}
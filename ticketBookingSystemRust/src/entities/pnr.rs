@@ -0,0 +1,125 @@
+// Imports Serde traits; a Pnr is stored as a plain JSON string.
+use serde::{Deserialize, Serialize};
+// Imports fmt for Display and the error messages.
+use std::fmt;
+// Imports the operating system's random number generator (re-exported by aes-gcm) for new PNRs.
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+// Imports SHA-256 to derive the PNRs of bookings made before PNRs existed.
+use sha2::{Digest, Sha256};
+
+// Characters a PNR is written with: digits and capitals without I, L, O and U (Crockford's base 32),
+// so a PNR copied by hand can't mix up 0 and O or 1, I and L.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// Number of characters in a PNR, the last one being the check character.
+pub const PNR_LEN: usize = 10;
+// Number of characters chosen at random (or derived), i.e. 45 bits.
+const BODY_LEN: usize = PNR_LEN - 1;
+
+// Why a value is not a valid PNR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PnrError {
+    // The value does not have exactly 10 characters; holds the number found.
+    WrongLength(usize),
+    // The value holds a character PNRs are never written with.
+    InvalidCharacter(char),
+    // The last character is not the check character of the others, i.e. the PNR has a typo.
+    CheckMismatch,
+}
+
+impl fmt::Display for PnrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PnrError::WrongLength(n) => write!(f, "must have {} characters, found {}", PNR_LEN, n),
+            PnrError::InvalidCharacter(c) => write!(f, "cannot contain '{}'", c),
+            PnrError::CheckMismatch => write!(f, "check character does not match; please check for typos"),
+        }
+    }
+}
+
+impl std::error::Error for PnrError {}
+
+// A booking's passenger name record, e.g. "4K7QM2X9HT": nine random characters followed by a Luhn
+// mod 32 check character, which catches any single mistyped character and most swapped neighbours.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Pnr(String);
+
+impl Pnr {
+    // Parses user input case-insensitively, ignoring spaces and hyphens, and reading O as 0 and I or
+    // L as 1 the way they are commonly mistyped.
+    pub fn parse(input: &str) -> Result<Self, PnrError> {
+        let mut values = Vec::new();
+        for c in input.trim().chars().filter(|c| *c != ' ' && *c != '-') {
+            let c = match c.to_ascii_uppercase() {
+                'O' => '0',
+                'I' | 'L' => '1',
+                c => c,
+            };
+            let value = ALPHABET.iter().position(|a| *a as char == c).ok_or(PnrError::InvalidCharacter(c))?;
+            values.push(value);
+        }
+        if values.len() != PNR_LEN {
+            return Err(PnrError::WrongLength(values.len()));
+        }
+        if check_value(&values[..BODY_LEN]) != values[BODY_LEN] {
+            return Err(PnrError::CheckMismatch);
+        }
+        Ok(Pnr(values.iter().map(|v| ALPHABET[*v] as char).collect()))
+    }
+
+    // Draws a random PNR. Callers make it unique by drawing again while `taken` holds it, under the
+    // lock of the store it is saved to.
+    pub fn generate(mut taken: impl FnMut(&Pnr) -> bool) -> Self {
+        loop {
+            let pnr = Self::from_bits(OsRng.next_u64());
+            if !taken(&pnr) {
+                return pnr;
+            }
+        }
+    }
+
+    // Derives a PNR from `seed`, so that bookings made before PNRs existed get the same PNR every
+    // time they are migrated.
+    pub fn from_seed(seed: &str) -> Self {
+        let digest = Sha256::digest(seed.as_bytes());
+        Self::from_bits(u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 has 32 bytes")))
+    }
+
+    // Returns the PNR as stored and printed.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Builds a PNR from the low 45 bits of `bits`, adding the check character.
+    fn from_bits(bits: u64) -> Self {
+        let values: Vec<usize> = (0..BODY_LEN).map(|i| ((bits >> (5 * i)) & 0x1f) as usize).collect();
+        let check = check_value(&values);
+        Pnr(values.iter().chain([&check]).map(|v| ALPHABET[*v] as char).collect())
+    }
+}
+
+// Wraps a stored value without checking it; use parse for user input.
+impl From<&str> for Pnr {
+    fn from(value: &str) -> Self {
+        Pnr(value.to_string())
+    }
+}
+
+// Renders the PNR as printed on a ticket.
+impl fmt::Display for Pnr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Returns the Luhn mod 32 check value of `values`: doubling every second value from the right and
+// summing the base-32 digits of the results, the check value brings the total to a multiple of 32.
+fn check_value(values: &[usize]) -> usize {
+    let mut sum = 0;
+    for (i, value) in values.iter().rev().enumerate() {
+        let addend = if i % 2 == 0 { value * 2 } else { *value };
+        sum += addend / 32 + addend % 32;
+    }
+    (32 - sum % 32) % 32
+}
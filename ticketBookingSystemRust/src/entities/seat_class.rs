@@ -0,0 +1,106 @@
+// Imports Serde traits; a class is stored as its code, e.g. "3A".
+use serde::{Deserialize, Serialize};
+// Imports fmt for the "AC 3 Tier (3A)" style formatting.
+use std::fmt;
+// Imports the seat kinds making up each class's coaches.
+use super::seat_layout::{SeatKind, SLEEPER_BAY};
+
+// The travel class of a coach, as sold on Indian Railways.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SeatClass {
+    #[serde(rename = "SL")]
+    Sleeper, // Non-AC sleeper berths, coaches S1, S2, ...
+    #[serde(rename = "3A")]
+    ThirdAc, // AC 3 tier berths, coaches B1, B2, ...
+    #[serde(rename = "2A")]
+    SecondAc, // AC 2 tier berths, coaches A1, A2, ...
+    #[serde(rename = "1A")]
+    FirstAc, // AC first class cabins, coaches H1, H2, ...
+    #[serde(rename = "CC")]
+    ChairCar, // AC chair car seats, coaches C1, C2, ...
+}
+
+// Every class, in the order they are listed to users.
+pub const SEAT_CLASSES: [SeatClass; 5] =
+    [SeatClass::Sleeper, SeatClass::ThirdAc, SeatClass::SecondAc, SeatClass::FirstAc, SeatClass::ChairCar];
+
+// Berths of one AC 2 tier bay: two facing stacks of two and two side berths.
+const SECOND_AC_BAY: [SeatKind; 6] = [
+    SeatKind::LowerBerth,
+    SeatKind::UpperBerth,
+    SeatKind::LowerBerth,
+    SeatKind::UpperBerth,
+    SeatKind::SideLower,
+    SeatKind::SideUpper,
+];
+// Berths of one AC first class cabin.
+const FIRST_AC_CABIN: [SeatKind; 4] = [SeatKind::LowerBerth, SeatKind::UpperBerth, SeatKind::LowerBerth, SeatKind::UpperBerth];
+// Seats of one chair car row: three, the aisle, then two.
+const CHAIR_CAR_ROW: [SeatKind; 5] = [SeatKind::Window, SeatKind::Middle, SeatKind::Aisle, SeatKind::Aisle, SeatKind::Window];
+
+impl SeatClass {
+    // Parses a class as typed by a user: its code ("3A", "sl") or its name ("Sleeper", "chair car").
+    pub fn parse(input: &str) -> Option<SeatClass> {
+        let input: String = input.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        SEAT_CLASSES
+            .into_iter()
+            .find(|class| input == class.code() || input == class.name().replace(' ', "").to_ascii_uppercase())
+    }
+
+    // Returns the code printed on tickets and stored in the database, e.g. "3A".
+    pub fn code(self) -> &'static str {
+        match self {
+            SeatClass::Sleeper => "SL",
+            SeatClass::ThirdAc => "3A",
+            SeatClass::SecondAc => "2A",
+            SeatClass::FirstAc => "1A",
+            SeatClass::ChairCar => "CC",
+        }
+    }
+
+    // Returns the class's full name, e.g. "AC 3 Tier".
+    pub fn name(self) -> &'static str {
+        match self {
+            SeatClass::Sleeper => "Sleeper",
+            SeatClass::ThirdAc => "AC 3 Tier",
+            SeatClass::SecondAc => "AC 2 Tier",
+            SeatClass::FirstAc => "AC First Class",
+            SeatClass::ChairCar => "Chair Car",
+        }
+    }
+
+    // Returns the letter the codes of the class's coaches start with, e.g. 'B' for B1, B2, ...
+    pub fn coach_prefix(self) -> char {
+        match self {
+            SeatClass::Sleeper => 'S',
+            SeatClass::ThirdAc => 'B',
+            SeatClass::SecondAc => 'A',
+            SeatClass::FirstAc => 'H',
+            SeatClass::ChairCar => 'C',
+        }
+    }
+
+    // Returns true if the class sells berths, false for the seats of a chair car.
+    pub fn has_berths(self) -> bool {
+        self != SeatClass::ChairCar
+    }
+
+    // Returns the standard layout of a coach of the class: its number of rows (bays, cabins or seat
+    // rows) and the kinds of the seats in each row.
+    pub fn coach_layout(self) -> (usize, &'static [SeatKind]) {
+        match self {
+            SeatClass::Sleeper => (9, &SLEEPER_BAY),
+            SeatClass::ThirdAc => (8, &SLEEPER_BAY),
+            SeatClass::SecondAc => (8, &SECOND_AC_BAY),
+            SeatClass::FirstAc => (6, &FIRST_AC_CABIN),
+            SeatClass::ChairCar => (15, &CHAIR_CAR_ROW),
+        }
+    }
+}
+
+// Renders the class as "AC 3 Tier (3A)".
+impl fmt::Display for SeatClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.code())
+    }
+}
@@ -0,0 +1,400 @@
+// Imports Serde traits for JSON serialization/deserialization.
+use serde::{Deserialize, Serialize};
+// Imports fmt for the "S3-42" style seat number formatting.
+use std::fmt;
+// Imports the PNR stored with every booked seat.
+use super::pnr::Pnr;
+// Imports the class of each coach.
+use super::seat_class::SeatClass;
+
+// Composition given to a train or vehicle when it is first booked: how many coaches of each class,
+// front to back.
+pub const DEFAULT_COMPOSITION: &[(SeatClass, usize)] = &[
+    (SeatClass::Sleeper, 3),  // S1-S3.
+    (SeatClass::ThirdAc, 2),  // B1-B2.
+    (SeatClass::SecondAc, 1), // A1.
+    (SeatClass::FirstAc, 1),  // H1.
+    (SeatClass::ChairCar, 1), // C1.
+];
+
+// What kind of seat or berth a seat is, as asked for by passengers: where a seat sits in its row, or
+// which berth of a sleeper bay it is.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SeatKind {
+    Window,      // Seat next to a window.
+    Middle,      // Seat between a window and an aisle seat.
+    Aisle,       // Seat next to the aisle.
+    LowerBerth,  // Bottom berth of a bay.
+    MiddleBerth, // Middle berth of a bay.
+    UpperBerth,  // Top berth of a bay.
+    SideLower,   // Bottom berth along the corridor.
+    SideUpper,   // Top berth along the corridor.
+}
+
+// Every seat kind, in the order they are listed to users.
+pub const SEAT_KINDS: [SeatKind; 8] = [
+    SeatKind::Window,
+    SeatKind::Middle,
+    SeatKind::Aisle,
+    SeatKind::LowerBerth,
+    SeatKind::MiddleBerth,
+    SeatKind::UpperBerth,
+    SeatKind::SideLower,
+    SeatKind::SideUpper,
+];
+
+// Berths of one sleeper bay, in berth number order: two facing stacks of three and two side berths.
+pub const SLEEPER_BAY: [SeatKind; 8] = [
+    SeatKind::LowerBerth,
+    SeatKind::MiddleBerth,
+    SeatKind::UpperBerth,
+    SeatKind::LowerBerth,
+    SeatKind::MiddleBerth,
+    SeatKind::UpperBerth,
+    SeatKind::SideLower,
+    SeatKind::SideUpper,
+];
+
+impl SeatKind {
+    // Parses a preference as typed by a user, e.g. "window", "Lower berth" or "side-upper".
+    pub fn parse(input: &str) -> Option<SeatKind> {
+        let input = input.to_ascii_lowercase();
+        let words: Vec<&str> = input.split(|c: char| c.is_whitespace() || c == '-' || c == '_').filter(|w| !w.is_empty()).collect();
+        let berth = words.contains(&"berth");
+        match (words.iter().filter(|w| **w != "seat" && **w != "berth").copied().collect::<Vec<_>>().as_slice(), berth) {
+            (["window"], false) => Some(SeatKind::Window),
+            (["middle"], false) => Some(SeatKind::Middle),
+            (["middle"], true) => Some(SeatKind::MiddleBerth),
+            (["aisle"], false) => Some(SeatKind::Aisle),
+            (["lower"], _) => Some(SeatKind::LowerBerth),
+            (["upper"], _) => Some(SeatKind::UpperBerth),
+            (["side", "lower"], _) => Some(SeatKind::SideLower),
+            (["side", "upper"], _) => Some(SeatKind::SideUpper),
+            _ => None,
+        }
+    }
+
+    // Returns the kind of the seat at `column` of a row `width` seats wide, the aisle running through
+    // the middle of the row (after the larger half when the width is odd).
+    pub fn in_row(column: usize, width: usize) -> SeatKind {
+        let aisle = width.div_ceil(2);
+        if column == 0 || column + 1 == width {
+            SeatKind::Window
+        } else if column + 1 == aisle || column == aisle {
+            SeatKind::Aisle
+        } else {
+            SeatKind::Middle
+        }
+    }
+}
+
+// Renders the kind as printed on tickets, e.g. "window seat" or "side lower berth".
+impl fmt::Display for SeatKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SeatKind::Window => "window seat",
+            SeatKind::Middle => "middle seat",
+            SeatKind::Aisle => "aisle seat",
+            SeatKind::LowerBerth => "lower berth",
+            SeatKind::MiddleBerth => "middle berth",
+            SeatKind::UpperBerth => "upper berth",
+            SeatKind::SideLower => "side lower berth",
+            SeatKind::SideUpper => "side upper berth",
+        })
+    }
+}
+
+// Identifies a single seat by coach code and seat number, displayed as "S3-42".
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SeatNumber {
+    pub coach: String, // Code of the coach holding the seat, e.g. "S3".
+    pub seat: u32,     // Seat number within the coach, starting at 1.
+}
+
+impl fmt::Display for SeatNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.coach, self.seat)
+    }
+}
+
+// Locates a booked seat: its printable number plus the row and column within its coach.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SeatPosition {
+    pub number: SeatNumber, // Printable seat number, e.g. "S3-42".
+    pub row: usize,         // Zero-based row within the coach.
+    pub column: usize,      // Zero-based seat index within the row.
+}
+
+// A single seat in a coach; `passenger` and `pnr` are None while the seat is free.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Seat {
+    pub number: u32,               // Seat number within the coach, starting at 1.
+    pub passenger: Option<String>, // Id of the booked passenger in the user registry, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pnr: Option<Pnr>,          // PNR of the booking holding the seat, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SeatKind>,    // Window, aisle, lower berth, ...; None if unknown.
+}
+
+// A coach of one class made of rows of seats, numbered row by row.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Coach {
+    pub code: String,         // Coach code printed on tickets, e.g. "S1".
+    pub class: SeatClass,     // Class every seat of the coach is sold in.
+    pub rows: Vec<Vec<Seat>>, // Rows of seats; every row of a coach has the same length.
+}
+
+impl Coach {
+    // Creates an empty coach of `class` with `rows` x `seats_per_row` seats numbered from 1. Rows of
+    // seats have window seats at their ends and an aisle through the middle; in berth classes the
+    // berths follow the class's bays in berth number order, whatever the row width.
+    pub fn new(class: SeatClass, code: String, rows: usize, seats_per_row: usize) -> Self {
+        let (_, bay) = class.coach_layout();
+        let rows = (0..rows)
+            .map(|r| {
+                (0..seats_per_row)
+                    .map(|s| {
+                        let index = r * seats_per_row + s;
+                        let kind = if class.has_berths() { bay[index % bay.len()] } else { SeatKind::in_row(s, seats_per_row) };
+                        Seat { number: (index + 1) as u32, passenger: None, pnr: None, kind: Some(kind) }
+                    })
+                    .collect()
+            })
+            .collect();
+        Coach { code, class, rows }
+    }
+
+    // Creates an empty coach of `class` with `bays` bays (rows) holding the berths or seats of `bay`
+    // each, e.g. SLEEPER_BAY, numbered from 1.
+    pub fn with_berths(class: SeatClass, code: String, bays: usize, bay: &[SeatKind]) -> Self {
+        let rows = (0..bays)
+            .map(|r| {
+                bay.iter()
+                    .enumerate()
+                    .map(|(s, kind)| Seat {
+                        number: (r * bay.len() + s + 1) as u32,
+                        passenger: None,
+                        pnr: None,
+                        kind: Some(*kind),
+                    })
+                    .collect()
+            })
+            .collect();
+        Coach { code, class, rows }
+    }
+
+    // Creates the `number`th empty coach of `class` in the class's standard layout, e.g. B2.
+    pub fn of_class(class: SeatClass, number: usize) -> Self {
+        let (bays, bay) = class.coach_layout();
+        Self::with_berths(class, format!("{}{}", class.coach_prefix(), number), bays, bay)
+    }
+
+    // Iterates over every seat of the coach in seat number order.
+    pub fn seats(&self) -> impl Iterator<Item = &Seat> {
+        self.rows.iter().flatten()
+    }
+}
+
+// The fixed seat layout carried by a Train or Vehicle: its composition of coaches, front to back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeatLayout {
+    pub coaches: Vec<Coach>,
+}
+
+impl SeatLayout {
+    // Creates an empty layout of `coaches` coaches of `class` (e.g. S1, S2, ... for Sleeper) with
+    // `rows` x `seats_per_row` seats each.
+    pub fn new(class: SeatClass, coaches: usize, rows: usize, seats_per_row: usize) -> Self {
+        SeatLayout {
+            coaches: (1..=coaches)
+                .map(|c| Coach::new(class, format!("{}{}", class.coach_prefix(), c), rows, seats_per_row))
+                .collect(),
+        }
+    }
+
+    // Creates an empty layout of the coaches of `composition`, in the standard layout of their class
+    // and numbered per class, e.g. [(Sleeper, 2), (ThirdAc, 1)] gives S1, S2, B1.
+    pub fn from_composition(composition: &[(SeatClass, usize)]) -> Self {
+        let mut coaches: Vec<Coach> = Vec::new();
+        for (class, count) in composition {
+            for _ in 0..*count {
+                let number = coaches.iter().filter(|c| c.class == *class).count() + 1;
+                coaches.push(Coach::of_class(*class, number));
+            }
+        }
+        SeatLayout { coaches }
+    }
+
+    // Lists the classes of the layout's coaches, in the order they first appear.
+    pub fn classes(&self) -> Vec<SeatClass> {
+        let mut classes: Vec<SeatClass> = Vec::new();
+        for coach in &self.coaches {
+            if !classes.contains(&coach.class) {
+                classes.push(coach.class);
+            }
+        }
+        classes
+    }
+
+    // Returns a copy of the layout holding only the coaches of `class`. Positions in it are valid in
+    // the whole layout, so allocators can be given one class to choose from.
+    pub fn of_class(&self, class: SeatClass) -> SeatLayout {
+        SeatLayout { coaches: self.coaches.iter().filter(|c| c.class == class).cloned().collect() }
+    }
+
+    // Total number of seats across all coaches.
+    pub fn capacity(&self) -> usize {
+        self.coaches.iter().map(|c| c.seats().count()).sum()
+    }
+
+    // Number of seats that are still free.
+    pub fn available(&self) -> usize {
+        self.coaches
+            .iter()
+            .flat_map(|c| c.seats())
+            .filter(|s| s.passenger.is_none())
+            .count()
+    }
+
+    // Seats each user at the position of the same index under booking `pnr`, or nobody if any of
+    // the positions is not a free seat. Returns the seat numbers in the order of `user_ids`.
+    pub fn assign_at(&mut self, positions: &[SeatPosition], user_ids: &[&str], pnr: &Pnr) -> Option<Vec<SeatNumber>> {
+        let all_free = positions.iter().all(|p| self.seat_at(p).is_some_and(|s| s.passenger.is_none()));
+        let distinct = positions.iter().enumerate().all(|(i, p)| !positions[..i].contains(p));
+        if positions.len() != user_ids.len() || !all_free || !distinct {
+            return None;
+        }
+        let mut numbers = Vec::new();
+        for (position, user_id) in positions.iter().zip(user_ids) {
+            let seat = self.seat_at_mut(position).expect("positions were checked above");
+            seat.passenger = Some(user_id.to_string());
+            seat.pnr = Some(pnr.clone());
+            numbers.push(position.number.clone());
+        }
+        Some(numbers)
+    }
+
+    // Returns the seat at `position`, if the layout has it.
+    pub fn seat_at(&self, position: &SeatPosition) -> Option<&Seat> {
+        let coach = self.coaches.iter().find(|c| c.code == position.number.coach)?;
+        coach.rows.get(position.row)?.get(position.column).filter(|s| s.number == position.number.seat)
+    }
+
+    // Returns the seat at `position` for changing, if the layout has it.
+    fn seat_at_mut(&mut self, position: &SeatPosition) -> Option<&mut Seat> {
+        let coach = self.coaches.iter_mut().find(|c| c.code == position.number.coach)?;
+        coach.rows.get_mut(position.row)?.get_mut(position.column).filter(|s| s.number == position.number.seat)
+    }
+
+    // Lists the positions of every free seat, in layout order.
+    pub fn free_seats(&self) -> Vec<SeatPosition> {
+        let mut free = Vec::new();
+        for coach in &self.coaches {
+            for (row_i, row) in coach.rows.iter().enumerate() {
+                for (col_i, seat) in row.iter().enumerate() {
+                    if seat.passenger.is_none() {
+                        let number = SeatNumber {
+                            coach: coach.code.clone(),
+                            seat: seat.number,
+                        };
+                        free.push(SeatPosition { number, row: row_i, column: col_i });
+                    }
+                }
+            }
+        }
+        free
+    }
+
+    // Frees every seat held by the user, under any PNR, returning the numbers of the released seats.
+    pub fn release(&mut self, user_id: &str) -> Vec<SeatNumber> {
+        self.release_matching(|seat| seat.passenger.as_deref() == Some(user_id))
+    }
+
+    // Frees every seat of the booking `pnr`, returning the numbers of the released seats.
+    pub fn release_pnr(&mut self, pnr: &Pnr) -> Vec<SeatNumber> {
+        self.release_matching(|seat| seat.pnr.as_ref() == Some(pnr))
+    }
+
+    // Frees the seat of one passenger of booking `pnr`, returning its number (empty if the user
+    // holds no seat under that PNR).
+    pub fn release_passenger(&mut self, pnr: &Pnr, user_id: &str) -> Vec<SeatNumber> {
+        self.release_matching(|seat| seat.pnr.as_ref() == Some(pnr) && seat.passenger.as_deref() == Some(user_id))
+    }
+
+    // Frees every seat for which `matches` returns true.
+    fn release_matching(&mut self, matches: impl Fn(&Seat) -> bool) -> Vec<SeatNumber> {
+        let mut released = Vec::new();
+        for coach in self.coaches.iter_mut() {
+            for seat in coach.rows.iter_mut().flatten() {
+                if seat.passenger.is_some() && matches(seat) {
+                    seat.passenger = None;
+                    seat.pnr = None;
+                    released.push(SeatNumber {
+                        coach: coach.code.clone(),
+                        seat: seat.number,
+                    });
+                }
+            }
+        }
+        released
+    }
+
+    // Lists every occupied seat, in layout order.
+    pub fn occupied(&self) -> Vec<(SeatNumber, &Seat)> {
+        let mut occupied = Vec::new();
+        for coach in &self.coaches {
+            for seat in coach.seats().filter(|s| s.passenger.is_some()) {
+                occupied.push((SeatNumber { coach: coach.code.clone(), seat: seat.number }, seat));
+            }
+        }
+        occupied
+    }
+
+    // Frees every seat, keeping the layout itself.
+    pub fn clear_passengers(&mut self) {
+        for seat in self.coaches.iter_mut().flat_map(|c| c.rows.iter_mut().flatten()) {
+            seat.passenger = None;
+            seat.pnr = None;
+        }
+    }
+
+    // Returns true if any seat is booked under `pnr`.
+    pub fn holds_pnr(&self, pnr: &Pnr) -> bool {
+        self.coaches.iter().flat_map(|c| c.seats()).any(|s| s.pnr.as_ref() == Some(pnr))
+    }
+
+    // Lists the positions of the seats held by the user.
+    pub fn seats_of(&self, user_id: &str) -> Vec<SeatPosition> {
+        self.booked_seats(|seat| seat.passenger.as_deref() == Some(user_id))
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    // Lists the booked seats for which `matches` returns true, with their positions, in layout order.
+    pub fn booked_seats(&self, matches: impl Fn(&Seat) -> bool) -> Vec<(SeatPosition, &Seat)> {
+        let mut booked = Vec::new();
+        for coach in &self.coaches {
+            for (row_i, row) in coach.rows.iter().enumerate() {
+                for (col_i, seat) in row.iter().enumerate() {
+                    if seat.passenger.is_some() && matches(seat) {
+                        let number = SeatNumber {
+                            coach: coach.code.clone(),
+                            seat: seat.number,
+                        };
+                        booked.push((SeatPosition { number, row: row_i, column: col_i }, seat));
+                    }
+                }
+            }
+        }
+        booked
+    }
+}
+
+// Implements Default for SeatLayout using the default composition.
+impl Default for SeatLayout {
+    fn default() -> Self {
+        SeatLayout::from_composition(DEFAULT_COMPOSITION)
+    }
+}
@@ -1,14 +1,66 @@
 // Imports Serde traits for JSON serialization/deserialization.
 use serde::{Deserialize, Serialize};
+// Imports fmt for the masked Display and Debug output.
+use std::fmt;
+// Imports the Aadhar number type stored with each user.
+use super::aadhar::AadharNumber;
 
 // Defines the User struct, equivalent to the C++ User struct in user.hpp.
-// Derives Serialize and Deserialize for JSON compatibility, Clone for copying, and Debug for printing.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Derives Serialize and Deserialize for JSON compatibility and Clone for copying. Display and Debug
+// are implemented by hand so the Aadhar number is masked wherever a user is printed or logged.
+#[derive(Serialize, Deserialize, Clone)]
 pub struct User {
+    #[serde(rename = "userId")]
     pub user_id: String,       // Stores the user's unique identifier, equivalent to C++'s userId: string.
     pub name: String,          // Stores the user's name, equivalent to C++'s name: string.
-    pub aadhar_card: String,   // Stores the user's Aadhar card number, equivalent to C++'s aadharCard: string.
+    #[serde(rename = "aadharCard")]
+    pub aadhar_card: AadharNumber, // Stores the user's Aadhar card number, equivalent to C++'s aadharCard: string.
     // Note: The C++ User struct has a vector<Vehicle*> vehicles field, but it's unused in the provided code.
     // Omitted here to avoid complex ownership handling (e.g., Rc/Arc for pointers).
     // Could be added as: pub vehicles: Vec<Rc<Vehicle>> if needed, with proper imports.
-}
\ No newline at end of file
+}
+
+impl User {
+    // Returns the Aadhar number with all but the last 4 digits hidden, e.g. "XXXX XXXX 2346".
+    pub fn masked_aadhar(&self) -> String {
+        self.aadhar_card.masked()
+    }
+
+    // Access to the full Aadhar number, for staff checking a passenger's documents.
+    // Everything else prints users through Display or Debug, which mask it.
+    pub fn reveal_aadhar(&self) -> &str {
+        self.aadhar_card.reveal()
+    }
+
+    // Renders the user with the full Aadhar number; see reveal_aadhar.
+    pub fn revealed(&self) -> RevealedUser<'_> {
+        RevealedUser(self)
+    }
+}
+
+// Renders a user as "Alice (id: u1, Aadhar: XXXX XXXX 9012)".
+impl fmt::Display for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (id: {}, Aadhar: {})", self.name, self.user_id, self.masked_aadhar())
+    }
+}
+
+// Implements Debug with the Aadhar number masked, so {:?} in logs and panics cannot leak it.
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("user_id", &self.user_id)
+            .field("name", &self.name)
+            .field("aadhar_card", &self.masked_aadhar())
+            .finish()
+    }
+}
+
+// A user rendered with the full Aadhar number, returned by User::revealed.
+pub struct RevealedUser<'a>(&'a User);
+
+impl fmt::Display for RevealedUser<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (id: {}, Aadhar: {})", self.0.name, self.0.user_id, self.0.reveal_aadhar())
+    }
+}
@@ -1,12 +1,27 @@
 // Declares the entities module, containing user, train, and vehicle definitions.
 pub mod entities {
     pub mod user;    // Includes the user module (equivalent to user.hpp).
+    pub mod aadhar;  // Includes the validated Aadhar number type stored with each user.
     pub mod train;   // Includes the train module (equivalent to train.hpp).
     pub mod vehicle; // Includes the vehicle module (equivalent to vehicle.hpp).
+    pub mod seat_layout; // Includes the seat layout module (coaches, rows and seats).
+    pub mod seat_class;  // Includes the travel classes coaches are sold in (SL, 3A, 2A, 1A, CC).
+    pub mod pnr;     // Includes the PNR identifying each booking.
 }
 
 // Declares the service module, containing file I/O and booking service logic.
 pub mod service {
     pub mod file_io;        // Includes the file_io module (equivalent to fileIO.hpp/cpp).
+    pub mod sqlite_io;      // Includes the SQLite storage backend and the db.json importer.
+    pub mod journal_io;     // Includes the append-only journal storage backend with snapshots.
+    pub mod memory_io;      // Includes the in-memory storage backend for tests and simulations.
+    pub mod encryption;     // Includes encryption of Aadhar numbers at rest and key rotation.
+    pub mod backup;         // Includes point-in-time backups with checksum manifests, for every backend.
+    pub mod config;         // Includes the storage configuration (backend, database path, lock timeout).
+    pub mod user_registry;  // Includes the registry of users that seats refer to by id.
     pub mod booking_service; // Includes the booking_service module (equivalent to bookingService.hpp/cpp).
+    pub mod booking_request; // Includes the multi-passenger booking request taken by book.
+    pub mod seat_allocator;  // Includes the strategies choosing which seats a booking gets.
+    pub mod booking_error;   // Includes the BookingError type returned by the booking service.
+    pub mod booking_view;    // Includes the structured booking view returned by get_booking.
 }
\ No newline at end of file
@@ -1,15 +1,40 @@
+// Imports env for command-line subcommands.
+use std::env;
 // Imports io module for console input/output, equivalent to C++'s std::iostream.
 use std::io::{self, Write};
+// Imports Path for key files given on the command line.
+use std::path::Path;
 // Imports User, Vehicle, and BookingService from the library.
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::seat_class::{SeatClass, SEAT_CLASSES};
+use train_booking::entities::seat_layout::{SeatKind, SEAT_KINDS};
 use train_booking::entities::user::User;
 use train_booking::entities::vehicle::Vehicle;
 use train_booking::service::booking_service::{BookingService, BookingServiceImpl};
+use train_booking::service::booking_request::{BookingRequest, MAX_PASSENGERS};
+use train_booking::service::config::{Backend, StorageConfig};
+use train_booking::service::file_io::{FileIOImpl, DEFAULT_DB_PATH};
+use train_booking::service::user_registry::UserRegistry;
+use train_booking::service::sqlite_io::{self, SqliteIOImpl, DEFAULT_SQLITE_PATH};
+use train_booking::service::journal_io::JournalIOImpl;
+use train_booking::service::backup;
+use train_booking::service::encryption::{self, AadharCipher};
 
 // Main function, returning io::Result for error handling.
 fn main() -> io::Result<()> {
+    // Runs a maintenance subcommand instead of the interactive prompt when one is given.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        return run_command(command, &args[1..]);
+    }
+
+    // Creates a BookingService for Vehicle, matching C++'s use of BookingService<Vehicle>,
+    // backed by the storage chosen in the config file or environment.
+    let config = StorageConfig::load()?;
+    let booking_service = BookingServiceImpl::with_storage(config.open::<Vehicle>()?, config.open::<User>()?);
+
     let mut user_id = String::new(); // Initializes a string for user ID, equivalent to C++'s string userId.
     let mut name = String::new();    // Initializes a string for user name.
-    let mut aadhar_card = String::new(); // Initializes a string for Aadhar card number.
 
     print!("Enter User ID: "); // Prompts for user ID, equivalent to C++'s cout.
     io::stdout().flush()?;     // Flushes stdout to ensure prompt is displayed before input.
@@ -21,13 +46,16 @@ fn main() -> io::Result<()> {
     io::stdin().read_line(&mut name)?;
     name = name.trim().to_string(); // Trims and converts to String, allowing multi-word names.
 
-    print!("Enter Aadhar Card Number: "); // Prompts for Aadhar card number.
-    io::stdout().flush()?;
-    io::stdin().read_line(&mut aadhar_card)?;
-    aadhar_card = aadhar_card.trim().to_string(); // Trims and converts to String.
+    let aadhar_card = prompt_aadhar()?;
 
     println!("Enter the option: "); // Prompts for option selection.
     println!("Enter 1 to book the train"); // Displays available option, matching C++.
+    println!("Enter 2 to cancel a booking"); // Displays the cancel option.
+    println!("Enter 3 to view a booking");   // Displays the view option.
+    println!("Enter 4 to cancel a booking by PNR"); // Displays the cancel-by-PNR option.
+    println!("Enter 5 to view a booking by PNR");   // Displays the view-by-PNR option.
+    println!("Enter 6 to cancel some passengers of a PNR"); // Displays the partial cancellation option.
+    println!("Enter 7 to see the free seats of a train by class"); // Displays the availability option.
     let mut option = String::new(); // Initializes a string for the option input.
     io::stdin().read_line(&mut option)?; // Reads the option as a string.
     let option: i32 = option.trim().parse().unwrap_or(0); // Parses to i32, defaults to 0 if invalid.
@@ -72,15 +100,337 @@ fn main() -> io::Result<()> {
                 aadhar_card,
             };
 
-            // Creates a BookingService for Vehicle, matching C++'s use of BookingService<Vehicle>.
-            let booking_service = BookingServiceImpl::<Vehicle>::new();
-            // Books the train (as a Vehicle), propagating any file I/O errors.
-            booking_service.book(train_id, user, train_name, source, destination)?;
+            // Collects the passengers travelling with the user, booked together under one PNR.
+            let mut passengers = vec![user];
+            while passengers.len() < MAX_PASSENGERS {
+                let user_id = prompt("Add a co-passenger? Enter their User ID (leave blank to finish): ")?;
+                if user_id.is_empty() {
+                    break;
+                }
+                let name = prompt("Enter Name: ")?;
+                passengers.push(User { user_id, name, aadhar_card: prompt_aadhar()? });
+            }
 
-            println!("Train booked successfully!"); // Outputs success message.
+            // Books the train (as a Vehicle), reporting the PNR and seats or the reason it failed.
+            let mut request = BookingRequest::new(train_id, train_name, source, destination, passengers);
+            if let Some(class) = prompt_class()? {
+                request = request.with_class(class);
+            }
+            if let Some(kind) = prompt_preference()? {
+                request = request.with_preference(kind);
+            }
+            match booking_service.book(request) {
+                Ok(ticket) => {
+                    println!("Train booked successfully! {}", ticket); // Outputs the PNR and seats.
+                }
+                Err(e) => eprintln!("Booking failed: {}", e),
+            }
+        }
+        2 => { // Case for cancelling a booking.
+            let train_id = prompt("Enter Train ID: ")?;
+            match booking_service.cancel_booking(train_id, user_id) {
+                Ok(released) => {
+                    let seats: Vec<String> = released.iter().map(|s| s.to_string()).collect();
+                    println!("Cancelled booking, released seats: {}", seats.join(", "));
+                }
+                Err(e) => eprintln!("Cancellation failed: {}", e),
+            }
+        }
+        3 => { // Case for viewing a booking.
+            let train_id = prompt("Enter Train ID: ")?;
+            if let Err(e) = booking_service.print_booking(train_id, user_id) {
+                eprintln!("Could not show booking: {}", e);
+            }
+        }
+        4 => { // Case for cancelling a booking by its PNR.
+            let pnr = prompt("Enter PNR: ")?;
+            match booking_service.cancel_by_pnr(pnr) {
+                Ok(released) => {
+                    let seats: Vec<String> = released.iter().map(|s| s.to_string()).collect();
+                    println!("Cancelled booking, released seats: {}", seats.join(", "));
+                }
+                Err(e) => eprintln!("Cancellation failed: {}", e),
+            }
+        }
+        5 => { // Case for viewing a booking by its PNR.
+            let pnr = prompt("Enter PNR: ")?;
+            if let Err(e) = booking_service.print_booking_by_pnr(pnr) {
+                eprintln!("Could not show booking: {}", e);
+            }
+        }
+        6 => { // Case for cancelling some passengers of a booking, keeping the others.
+            let pnr = prompt("Enter PNR: ")?;
+            let user_ids = prompt("Enter the User IDs to cancel, separated by commas: ")?;
+            let user_ids = user_ids.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect();
+            match booking_service.cancel_passengers(pnr, user_ids) {
+                Ok(released) => {
+                    let seats: Vec<String> = released.iter().map(|s| s.to_string()).collect();
+                    println!("Cancelled passengers, released seats: {}", seats.join(", "));
+                }
+                Err(e) => eprintln!("Cancellation failed: {}", e),
+            }
+        }
+        7 => { // Case for showing the free seats of each class.
+            let train_id = prompt("Enter Train ID: ")?;
+            match booking_service.availability(train_id) {
+                Ok(classes) => {
+                    for class in classes {
+                        println!("{}", class);
+                    }
+                }
+                Err(e) => eprintln!("Could not show availability: {}", e),
+            }
         }
         _ => println!("Invalid option"), // Default case for invalid input, matching C++.
     }
 
     Ok(()) // Returns success for the main function.
-}
\ No newline at end of file
+}
+
+// Prints a prompt, reads one line from stdin and returns it trimmed.
+fn prompt(message: &str) -> io::Result<String> {
+    print!("{}", message);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+// Prompts for an Aadhar card number until a valid one (12 digits, Verhoeff check digit) is given.
+fn prompt_aadhar() -> io::Result<AadharNumber> {
+    loop {
+        print!("Enter Aadhar Card Number: ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no valid Aadhar number entered"));
+        }
+        match AadharNumber::parse(&input) {
+            Ok(aadhar) => return Ok(aadhar),
+            Err(e) => eprintln!("Invalid Aadhar number: {}. Please try again.", e),
+        }
+    }
+}
+
+// Prompts for an optional class until a known class or nothing is given.
+fn prompt_class() -> io::Result<Option<SeatClass>> {
+    let codes: Vec<&str> = SEAT_CLASSES.iter().map(|c| c.code()).collect();
+    loop {
+        let input = prompt(&format!("Class ({}; leave blank for any): ", codes.join(", ")))?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match SeatClass::parse(&input) {
+            Some(class) => return Ok(Some(class)),
+            None => eprintln!("Unknown class: {}. Please try again.", input),
+        }
+    }
+}
+
+// Prompts for an optional seat preference until a known kind of seat or nothing is given.
+fn prompt_preference() -> io::Result<Option<SeatKind>> {
+    let kinds: Vec<String> = SEAT_KINDS.iter().map(|k| k.to_string()).collect();
+    loop {
+        let input = prompt(&format!("Seat preference ({}; leave blank for none): ", kinds.join(", ")))?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match SeatKind::parse(&input) {
+            Some(kind) => return Ok(Some(kind)),
+            None => eprintln!("Unknown seat preference: {}. Please try again.", input),
+        }
+    }
+}
+
+// Runs a maintenance subcommand such as `main recover`.
+fn run_command(command: &str, options: &[String]) -> io::Result<()> {
+    let config = StorageConfig::load()?;
+    // recover and migrate repair and upgrade db.json; SQLite manages its own file format.
+    if matches!(command, "recover" | "migrate") && config.backend != Backend::Json {
+        eprintln!("`{}` only applies to the json backend", command);
+        return Ok(());
+    }
+    if command == "compact" && config.backend != Backend::Journal {
+        eprintln!("`compact` only applies to the journal backend");
+        return Ok(());
+    }
+    match command {
+        "recover" => {
+            // Quarantines a corrupt db.json and writes back the records that still parse.
+            let report = FileIOImpl::<Vehicle>::from_config(&config).recover()?;
+            match report.quarantined_to {
+                Some(path) => println!(
+                    "Database was corrupt; original moved to {}. Salvaged {} record(s), discarded {}.",
+                    path, report.salvaged, report.discarded
+                ),
+                None => println!("Database is healthy ({} record(s)); nothing to recover.", report.salvaged),
+            }
+        }
+        "migrate" => {
+            // Upgrades db.json to the current schema version; --dry-run only lists the steps.
+            let dry_run = options.iter().any(|o| o == "--dry-run");
+            let report = FileIOImpl::<Vehicle>::from_config(&config).migrate(dry_run)?;
+            if report.applied.is_empty() {
+                println!("Database is at schema version {}; nothing to migrate.", report.to_version);
+            } else {
+                let verb = if dry_run { "Would migrate" } else { "Migrated" };
+                println!("{} database from schema version {} to {}:", verb, report.from_version, report.to_version);
+                for step in &report.applied {
+                    println!("  - {}", step);
+                }
+            }
+            if !report.renamed.is_empty() {
+                // Passengers whose userId was taken by someone with a different Aadhar number.
+                let verb = if dry_run { "would be" } else { "were" };
+                println!("These passengers {} registered under a new id, since their user id belongs to someone else:", verb);
+                for user in &report.renamed {
+                    println!("  - {} ({}) -> {}, seated on {}", user.user_id, user.name, user.renamed_to, user.seated_on.join(", "));
+                }
+            }
+        }
+        "compact" => {
+            // Snapshots the journal's state, archives the journal and starts a new one.
+            let report = JournalIOImpl::<Vehicle>::from_config(&config).compact()?;
+            match report.archived_to {
+                Some(path) => println!(
+                    "Snapshot taken at entry {}; archived {} journal entries to {}.",
+                    report.seq, report.entries, path
+                ),
+                None => println!("Snapshot taken at entry {}; the journal was already empty.", report.seq),
+            }
+        }
+        "backup" => {
+            // Takes, lists or verifies backups of whichever backend is configured.
+            let backups_dir = config.backups_path();
+            match options.first().map(String::as_str) {
+                None | Some("create") => {
+                    let storage = config.open::<Vehicle>()?;
+                    let info = backup::create_backup(storage.as_ref(), config.backend.name(), &backups_dir)?;
+                    println!("Backup {} written to {} ({} file(s)).", info.id, info.path.display(), info.manifest.files.len());
+                }
+                Some("list") => {
+                    let backups = backup::list_backups(&backups_dir)?;
+                    if backups.is_empty() {
+                        println!("No backups in {}.", backups_dir.display());
+                    }
+                    for info in backups {
+                        let size: u64 = info.manifest.files.iter().map(|f| f.size).sum();
+                        println!("{:24} {:8} {} bytes", info.id, info.manifest.backend, size);
+                    }
+                }
+                Some("verify") => {
+                    let id = options.get(1).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: backup verify <id>"))?;
+                    let info = backup::find_backup(&backups_dir, id)?;
+                    let problems = backup::verify_backup(&info)?;
+                    if problems.is_empty() {
+                        println!("Backup {} is intact.", info.id);
+                    } else {
+                        println!("Backup {} is damaged:", info.id);
+                        for problem in problems {
+                            println!("  - {}", problem);
+                        }
+                    }
+                }
+                Some(other) => eprintln!("Unknown backup action: {} (available: create, list, verify <id>)", other),
+            }
+        }
+        "restore" => {
+            // Restores a backup, first backing up the current state so the restore can be undone.
+            let id = options.first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: restore <id>"))?;
+            let backups_dir = config.backups_path();
+            let info = backup::find_backup(&backups_dir, id)?;
+            let storage = config.open::<Vehicle>()?;
+            let undo = backup::create_backup(storage.as_ref(), config.backend.name(), &backups_dir)?;
+            backup::restore_backup(storage.as_ref(), config.backend.name(), &info)?;
+            println!("Restored backup {}. The previous state was saved as backup {}.", info.id, undo.id);
+        }
+        "generate-key" => {
+            // Writes a new random Aadhar encryption key to the given file.
+            let path = options.first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: generate-key <key file>"))?;
+            let cipher = encryption::generate_key_file(Path::new(path))?;
+            println!("Wrote key {} to {}. Set key_file or TRAIN_BOOKING_KEY_FILE to use it.", cipher.key_id(), path);
+        }
+        "rotate-key" => {
+            // Re-encrypts every stored Aadhar number from the configured key (if any) to a new key.
+            let path = options.first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: rotate-key <new key file>"))?;
+            let new = AadharCipher::from_key_file(Path::new(path))?;
+            let old = config.cipher()?;
+            let rewritten = encryption::rotate_key(config.open_unencrypted::<User>().as_ref(), old.as_ref(), &new)?;
+            println!(
+                "Re-encrypted {} Aadhar number(s) with key {}. Point key_file or TRAIN_BOOKING_KEY_FILE at {} now.",
+                rewritten, new.key_id(), path
+            );
+        }
+        "show-booking" => {
+            // Shows a booking with the passenger's full Aadhar number, by train and user or by PNR; the
+            // interactive prompt only ever shows it masked. There is no access check: whoever can run
+            // this can read the database, and with encryption on, also holds the key.
+            let booking_service = BookingServiceImpl::with_storage(config.open::<Vehicle>()?, config.open::<User>()?);
+            let shown = match options {
+                [train_id, user_id] => booking_service.print_booking_revealed(train_id.clone(), user_id.clone()),
+                [pnr] => booking_service.get_booking_by_pnr(pnr.clone()).map(|view| print!("{}", view.revealed())),
+                _ => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, "usage: show-booking <train id> <user id> | show-booking <pnr>"));
+                }
+            };
+            if let Err(e) = shown {
+                eprintln!("Could not show booking: {}", e);
+            }
+        }
+        "check-aadhar" => {
+            // Lists registered users whose Aadhar number is invalid, e.g. records saved before input was validated.
+            let mut invalid = 0;
+            for user in config.open::<User>()?.read_from_file()? {
+                if let Err(e) = user.aadhar_card.validate() {
+                    println!("{}: Aadhar number {}", user, e);
+                    invalid += 1;
+                }
+            }
+            match invalid {
+                0 => println!("Every stored Aadhar number is valid."),
+                n => println!("{} user(s) have an Aadhar number that failed validation.", n),
+            }
+        }
+        "rename-user" => {
+            // Changes a registered user's name once; every booking of theirs shows the new name.
+            let (Some(user_id), true) = (options.first(), options.len() > 1) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "usage: rename-user <user id> <new name>"));
+            };
+            let name = options[1..].join(" ");
+            let users = UserRegistry::new(config.open::<User>()?);
+            match users.rename(user_id, name.clone()) {
+                Ok(()) => println!("Renamed user {} to {}.", user_id, name),
+                Err(e) => eprintln!("Could not rename user: {}", e),
+            }
+        }
+        "import-json" => {
+            // Copies a db.json file into the SQLite database: the configured one when the SQLite
+            // backend is selected, "db.sqlite3" otherwise. Refuses a non-empty target without --force.
+            let force = options.iter().any(|o| o == "--force");
+            let source = match options.iter().find(|o| !o.starts_with("--")) {
+                Some(path) => path.into(),
+                None if config.backend == Backend::Json => config.database_path(),
+                None => DEFAULT_DB_PATH.into(),
+            };
+            let target = match config.backend {
+                Backend::Sqlite => SqliteIOImpl::from_config(&config),
+                Backend::Json | Backend::Journal => SqliteIOImpl::with_path(DEFAULT_SQLITE_PATH).with_busy_timeout(config.lock_timeout),
+            };
+            if !force && sqlite_io::has_entities(&target)? {
+                eprintln!("{} already holds data; rerun with --force to import into it anyway", target.path().display());
+                return Ok(());
+            }
+            let report = sqlite_io::import_json(&source, &target)?;
+            println!(
+                "Imported {} user(s), {} train(s) and {} vehicle(s) from {} into {}.",
+                report.users, report.trains, report.vehicles, source.display(), target.path().display()
+            );
+        }
+        _ => eprintln!(
+            "Unknown command: {} (available: recover, migrate [--dry-run], compact, import-json [db.json] [--force], backup [list|verify <id>], restore <id>, generate-key <file>, rotate-key <new key file>, show-booking <train id> <user id> | <pnr>, check-aadhar, rename-user <user id> <new name>)",
+            command
+        ),
+    }
+    Ok(())
+}
+
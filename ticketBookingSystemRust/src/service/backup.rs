@@ -0,0 +1,181 @@
+// Imports fs for the backup directories and io for errors.
+use std::fs;
+use std::io;
+// Imports Path and PathBuf for backup locations, and Component to check backup ids.
+use std::path::{Component, Path, PathBuf};
+// Imports time types to timestamp backups.
+use std::time::{SystemTime, UNIX_EPOCH};
+// Imports Serde traits for the manifest.
+use serde::{Deserialize, Serialize};
+// Imports the SHA-256 implementation used for checksums.
+use sha2::{Digest, Sha256};
+// Imports the storage trait whose backup_to/restore_from do the copying, and the Record trait its items implement.
+use super::file_io::{FileIO, Record};
+
+// Name of the manifest written into every backup directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// One file of a backup with its size and SHA-256 checksum.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BackupFile {
+    pub name: String,   // File name inside the backup directory.
+    pub size: u64,      // Size in bytes.
+    pub sha256: String, // Lowercase hex SHA-256 of the contents.
+}
+
+// Describes a backup: when it was taken, of which backend, and the checksums of its files.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub created_at: i64,        // Unix timestamp of the backup.
+    pub backend: String,        // Storage backend the files belong to ("json", "sqlite", ...).
+    pub files: Vec<BackupFile>, // The copied files.
+}
+
+// A backup found in the backups directory.
+#[derive(Debug)]
+pub struct BackupInfo {
+    pub id: String,               // Directory name, e.g. "2026-10-18T09-30-00Z".
+    pub path: PathBuf,            // The backup directory.
+    pub manifest: BackupManifest, // Its manifest.
+}
+
+// Takes a consistent copy of the store into "<backups_dir>/<UTC timestamp>/" with a checksum
+// manifest. The copy is assembled in a hidden directory and renamed into place once complete, so an
+// interrupted backup never shows up in the list.
+pub fn create_backup<T: Record>(storage: &dyn FileIO<Item = T>, backend: &str, backups_dir: &Path) -> io::Result<BackupInfo> {
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let mut id = format_utc(created_at);
+    let mut suffix = 1;
+    while backups_dir.join(&id).exists() {
+        id = format!("{}-{}", format_utc(created_at), suffix);
+        suffix += 1;
+    }
+    let partial = backups_dir.join(format!(".{}.partial", id));
+    fs::create_dir_all(&partial)?;
+    let result = (|| {
+        let names = storage.backup_to(&partial)?;
+        let files = names
+            .into_iter()
+            .map(|name| checksum(&partial, name))
+            .collect::<io::Result<Vec<_>>>()?;
+        let manifest = BackupManifest { created_at, backend: backend.to_string(), files };
+        fs::write(partial.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+        fs::rename(&partial, backups_dir.join(&id))?;
+        Ok(manifest)
+    })();
+    match result {
+        Ok(manifest) => Ok(BackupInfo { path: backups_dir.join(&id), id, manifest }),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&partial); // Best effort; the partial copy is hidden anyway.
+            Err(e)
+        }
+    }
+}
+
+// Lists the backups in `backups_dir`, oldest first. Directories without a readable manifest are skipped.
+pub fn list_backups(backups_dir: &Path) -> io::Result<Vec<BackupInfo>> {
+    let entries = match fs::read_dir(backups_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let id = entry.file_name().to_string_lossy().into_owned();
+        if id.starts_with('.') {
+            continue;
+        }
+        if let Ok(manifest) = read_manifest(&entry.path()) {
+            backups.push(BackupInfo { id, path: entry.path(), manifest });
+        }
+    }
+    backups.sort_by(|a, b| (a.manifest.created_at, &a.id).cmp(&(b.manifest.created_at, &b.id)));
+    Ok(backups)
+}
+
+// Finds a backup by id. The id must name a directory directly inside `backups_dir`, so ids with a
+// path separator or a "." / ".." component are refused before anything is read.
+pub fn find_backup(backups_dir: &Path, id: &str) -> io::Result<BackupInfo> {
+    let mut components = Path::new(id).components();
+    let single = matches!(components.next(), Some(Component::Normal(name)) if name == id) && components.next().is_none();
+    if !single || id.contains(['/', '\\']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid backup id {:?}", id)));
+    }
+    let path = backups_dir.join(id);
+    let manifest = read_manifest(&path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => io::Error::new(io::ErrorKind::NotFound, format!("no backup named {} in {}", id, backups_dir.display())),
+        _ => e,
+    })?;
+    Ok(BackupInfo { id: id.to_string(), path, manifest })
+}
+
+// Checks every file of a backup against its manifest, returning the problems found (empty if intact).
+pub fn verify_backup(backup: &BackupInfo) -> io::Result<Vec<String>> {
+    let mut problems = Vec::new();
+    for expected in &backup.manifest.files {
+        match checksum(&backup.path, expected.name.clone()) {
+            Ok(actual) if actual == *expected => {}
+            Ok(actual) if actual.size != expected.size => problems.push(format!(
+                "{}: size is {} bytes, expected {}",
+                expected.name, actual.size, expected.size
+            )),
+            Ok(_) => problems.push(format!("{}: checksum mismatch", expected.name)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => problems.push(format!("{}: missing", expected.name)),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(problems)
+}
+
+// Replaces the store with a backup after verifying it. Refuses damaged backups and backups taken
+// from another backend.
+pub fn restore_backup<T: Record>(storage: &dyn FileIO<Item = T>, backend: &str, backup: &BackupInfo) -> io::Result<()> {
+    if backup.manifest.backend != backend {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("backup {} was taken from the {} backend, not {}", backup.id, backup.manifest.backend, backend),
+        ));
+    }
+    let problems = verify_backup(backup)?;
+    if !problems.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("backup {} is damaged: {}", backup.id, problems.join("; ")),
+        ));
+    }
+    storage.restore_from(&backup.path)
+}
+
+// Reads the manifest of a backup directory.
+fn read_manifest(dir: &Path) -> io::Result<BackupManifest> {
+    let contents = fs::read(dir.join(MANIFEST_FILE))?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+// Computes the size and SHA-256 checksum of a file in a backup directory.
+fn checksum(dir: &Path, name: String) -> io::Result<BackupFile> {
+    let contents = fs::read(dir.join(&name))?;
+    let sha256 = Sha256::digest(&contents).iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(BackupFile { name, size: contents.len() as u64, sha256 })
+}
+
+// Formats a Unix timestamp as "YYYY-MM-DDTHH-MM-SSZ" (UTC), a sortable name that is valid on every filesystem.
+pub fn format_utc(secs: i64) -> String {
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Converts days since 1970-01-01 to a civil date (Howard Hinnant's days_from_civil inverse).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}Z",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60
+    )
+}
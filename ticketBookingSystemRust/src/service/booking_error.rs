@@ -0,0 +1,96 @@
+// Imports fmt for Display, and io for wrapping storage failures.
+use std::fmt;
+use std::io;
+// Imports the class named by class-specific errors.
+use super::super::entities::seat_class::SeatClass;
+
+// Defines the error returned by every BookingService method, so library callers can tell
+// what went wrong instead of relying on console output.
+#[derive(Debug)]
+pub enum BookingError {
+    // No train/vehicle is stored under the given id.
+    NotFound { kind: &'static str, id: String },
+    // The train/vehicle exists but the user holds no seat on it.
+    NoSuchPassenger { entity_id: String, user_id: String },
+    // No booking holds the given PNR (any more).
+    UnknownPnr { pnr: String },
+    // The user holds no seat under the given PNR.
+    NotOnPnr { pnr: String, user_id: String },
+    // Every seat of the train/vehicle, or of the requested class, is already taken.
+    SoldOut { entity_id: String, class: Option<SeatClass> },
+    // Fewer seats are free (in the requested class) than the booking has passengers; nothing was booked.
+    NotEnoughSeats { entity_id: String, class: Option<SeatClass>, requested: usize, available: usize },
+    // The train/vehicle has no coach of the requested class.
+    NoSuchClass { entity_id: String, class: SeatClass },
+    // The user id is already registered to someone with a different Aadhar number.
+    UserConflict { user_id: String },
+    // Reading or writing the database failed.
+    Storage(io::Error),
+    // The database contents could not be understood.
+    Corrupt(String),
+    // Another process held the database lock for longer than the configured timeout.
+    LockTimeout(String),
+    // The request itself was invalid (e.g. an empty id).
+    Validation(String),
+}
+
+impl fmt::Display for BookingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookingError::NotFound { kind, id } => write!(f, "{} with id {} not found", kind, id),
+            BookingError::NoSuchPassenger { entity_id, user_id } => {
+                write!(f, "No booking found for user {} on {}", user_id, entity_id)
+            }
+            BookingError::UnknownPnr { pnr } => write!(f, "No booking found with PNR {}", pnr),
+            BookingError::NotOnPnr { pnr, user_id } => write!(f, "User {} holds no seat under PNR {}", user_id, pnr),
+            BookingError::SoldOut { entity_id, class: None } => write!(f, "{} is sold out", entity_id),
+            BookingError::SoldOut { entity_id, class: Some(class) } => write!(f, "{} is sold out in {}", entity_id, class),
+            BookingError::NotEnoughSeats { entity_id, class: None, requested, available } => {
+                write!(f, "Only {} seat(s) left on {}, {} requested", available, entity_id, requested)
+            }
+            BookingError::NotEnoughSeats { entity_id, class: Some(class), requested, available } => {
+                write!(f, "Only {} seat(s) left in {} on {}, {} requested", available, class, entity_id, requested)
+            }
+            BookingError::NoSuchClass { entity_id, class } => write!(f, "{} has no {} coaches", entity_id, class),
+            BookingError::UserConflict { user_id } => {
+                write!(f, "User id {} is already registered with a different Aadhar number", user_id)
+            }
+            BookingError::Storage(e) => write!(f, "Storage error: {}", e),
+            BookingError::Corrupt(msg) => write!(f, "Corrupt database: {}", msg),
+            BookingError::LockTimeout(msg) => write!(f, "Database busy: {}", msg),
+            BookingError::Validation(msg) => write!(f, "Invalid request: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BookingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BookingError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl BookingError {
+    // Wraps the error in an io::Error so it can abort a FileIO::update_in_file cycle; the
+    // From<io::Error> conversion below unwraps it again unchanged.
+    pub fn into_io_error(self) -> io::Error {
+        io::Error::other(self)
+    }
+}
+
+// Converts I/O errors from the FileIO layer; unparseable data is reported as Corrupt and lock
+// timeouts as LockTimeout.
+impl From<io::Error> for BookingError {
+    fn from(e: io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<BookingError>()) {
+            return *e.into_inner().unwrap().downcast::<BookingError>().unwrap();
+        }
+        match e.kind() {
+            io::ErrorKind::InvalidData => BookingError::Corrupt(e.to_string()),
+            io::ErrorKind::TimedOut => BookingError::LockTimeout(e.to_string()),
+            _ => BookingError::Storage(e),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Imports the User struct for the passengers of a booking.
+use super::super::entities::user::User;
+// Imports the classes and seat kinds passengers can ask for.
+use super::super::entities::seat_class::SeatClass;
+use super::super::entities::seat_layout::SeatKind;
+// Imports the error returned for invalid requests.
+use super::booking_error::BookingError;
+
+// Most passengers a single booking (one PNR) can hold, as on Indian Railways tickets.
+pub const MAX_PASSENGERS: usize = 6;
+
+// A request to book seats for one or more passengers on a train/vehicle under a single PNR. The
+// name, source and destination are only used when the train/vehicle is booked for the first time.
+#[derive(Clone, Debug)]
+pub struct BookingRequest {
+    pub entity_id: String,            // Id of the train/vehicle to book.
+    pub name: String,                 // Train/vehicle name, stored when it is first booked.
+    pub source: String,               // Starting station, stored when it is first booked.
+    pub destination: String,          // Destination station, stored when it is first booked.
+    pub passengers: Vec<User>,        // Everyone travelling; each gets one seat.
+    pub class: Option<SeatClass>,     // Class to travel in; any coach if None.
+    pub preference: Option<SeatKind>, // Kind of seat the passengers would like, if they mind.
+}
+
+impl BookingRequest {
+    // Creates a request for `passengers` on the given train/vehicle.
+    pub fn new(entity_id: String, name: String, source: String, destination: String, passengers: Vec<User>) -> Self {
+        BookingRequest { entity_id, name, source, destination, passengers, class: None, preference: None }
+    }
+
+    // Asks for seats in coaches of `class` only.
+    pub fn with_class(mut self, class: SeatClass) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    // Asks for seats of `kind` (e.g. window seats or lower berths), given when enough are free.
+    pub fn with_preference(mut self, kind: SeatKind) -> Self {
+        self.preference = Some(kind);
+        self
+    }
+
+    // Rejects requests without an entity id or passengers, with too many passengers, or listing a
+    // passenger without an id, without a valid Aadhar number or more than once.
+    pub fn validate(&self) -> Result<(), BookingError> {
+        if self.entity_id.trim().is_empty() {
+            return Err(BookingError::Validation("entity id must not be empty".to_string()));
+        }
+        if self.passengers.is_empty() {
+            return Err(BookingError::Validation("a booking needs at least one passenger".to_string()));
+        }
+        if self.passengers.len() > MAX_PASSENGERS {
+            return Err(BookingError::Validation(format!(
+                "a booking can hold at most {} passengers, {} given",
+                MAX_PASSENGERS,
+                self.passengers.len()
+            )));
+        }
+        for (i, passenger) in self.passengers.iter().enumerate() {
+            if passenger.user_id.trim().is_empty() {
+                return Err(BookingError::Validation("user id must not be empty".to_string()));
+            }
+            if let Err(e) = passenger.aadhar_card.validate() {
+                return Err(BookingError::Validation(format!("Aadhar number of passenger {} {}", passenger.user_id, e)));
+            }
+            if self.passengers[..i].iter().any(|p| p.user_id == passenger.user_id) {
+                return Err(BookingError::Validation(format!("passenger {} is listed more than once", passenger.user_id)));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the ids of the passengers, in request order.
+    pub fn user_ids(&self) -> Vec<&str> {
+        self.passengers.iter().map(|p| p.user_id.as_str()).collect()
+    }
+}
@@ -4,218 +4,418 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use super::super::entities::user::User;
 use super::super::entities::vehicle::Vehicle;
 use super::super::entities::train::Train;
-// Imports FileIO trait and FileIOImpl for file operations.
-use super::file_io::{FileIO, FileIOImpl};
+use super::super::entities::seat_layout::{Seat, SeatLayout, SeatNumber, SeatPosition};
+use super::super::entities::pnr::Pnr;
+// Imports the FileIO storage trait, and FileIOImpl as the default storage.
+use super::file_io::{FileIO, FileIOImpl, Record};
+// Imports the error type returned by every BookingService method.
+use super::booking_error::BookingError;
+// Imports the structured booking view, its parts, and the ticket returned by book.
+use super::booking_view::{BookedSeat, BookingView, ClassAvailability, EntitySummary, SeatAssignment, Ticket};
+// Imports the multi-passenger request taken by book.
+use super::booking_request::BookingRequest;
+// Imports the registry holding the passengers that seats refer to.
+use super::user_registry::UserRegistry;
+// Imports the strategies choosing the seats of a booking.
+use super::seat_allocator::{KeepTogether, SeatAllocator};
+
+// Parses a PNR given by a caller, reporting a typo as an invalid request.
+fn parse_pnr(input: &str) -> Result<Pnr, BookingError> {
+    Pnr::parse(input).map_err(|e| BookingError::Validation(format!("PNR {} {}", input.trim(), e)))
+}
+
+// Seats every passenger of `request` in `seats` under `pnr`, at the seats chosen by `allocator` in
+// the requested class for the request's seat preference, or none of them if it finds no seats for everyone.
+fn seat_passengers(seats: &mut SeatLayout, request: &BookingRequest, pnr: Pnr, allocator: &dyn SeatAllocator) -> Result<Ticket, BookingError> {
+    let user_ids = request.user_ids();
+    let entity_id = request.entity_id.clone();
+    // The allocator only sees the coaches of the requested class.
+    let choices = match request.class {
+        Some(class) if !seats.classes().contains(&class) => return Err(BookingError::NoSuchClass { entity_id, class }),
+        Some(class) => seats.of_class(class),
+        None => seats.clone(),
+    };
+    let assigned = allocator
+        .allocate(&choices, user_ids.len(), request.preference)
+        .and_then(|positions| Some((seats.assign_at(&positions, &user_ids, &pnr)?, positions)));
+    let Some((numbers, positions)) = assigned else {
+        let class = request.class;
+        return Err(match choices.available() {
+            0 => BookingError::SoldOut { entity_id, class },
+            available => BookingError::NotEnoughSeats { entity_id, class, requested: user_ids.len(), available },
+        });
+    };
+    let assignments = user_ids
+        .iter()
+        .zip(numbers)
+        .zip(&positions)
+        .map(|((user_id, seat), position)| SeatAssignment {
+            user_id: user_id.to_string(),
+            seat,
+            kind: seats.seat_at(position).and_then(|s| s.kind),
+        })
+        .collect();
+    Ok(Ticket { pnr, seats: assignments, class: request.class, preference: request.preference })
+}
+
+// Reports the free seats of every class of `seats`, in the order the classes appear.
+fn class_availability(seats: &SeatLayout) -> Vec<ClassAvailability> {
+    seats
+        .classes()
+        .into_iter()
+        .map(|class| {
+            let coaches = seats.of_class(class);
+            ClassAvailability {
+                class,
+                coaches: coaches.coaches.iter().map(|c| c.code.clone()).collect(),
+                capacity: coaches.capacity(),
+                available: coaches.available(),
+            }
+        })
+        .collect()
+}
+
+// What the booking service needs of a stored entity beyond storage: how to create it on its first
+// booking, its seats, and how it is summarised in booking views. Implemented by Vehicle and Train.
+pub trait Bookable: Record {
+    // Name of the entity in errors and views, e.g. "Train".
+    const KIND: &'static str;
+    // Creates the entity booked for the first time by `request`, with the default, empty layout.
+    fn create(request: &BookingRequest, time: i64) -> Self;
+    // The entity's seat layout.
+    fn layout(&self) -> &SeatLayout;
+    fn layout_mut(&mut self) -> &mut SeatLayout;
+    // Summarises the entity for a booking view.
+    fn summary(&self) -> EntitySummary;
+}
+
+// Vehicles are booked as the C++ BookingService<Vehicle> did.
+impl Bookable for Vehicle {
+    const KIND: &'static str = "Vehicle";
+
+    fn create(request: &BookingRequest, time: i64) -> Self {
+        Vehicle {
+            vehicle_id: request.entity_id.clone(), // Sets vehicle_id from input, matching C++.
+            name: request.name.clone(),
+            source: request.source.clone(),
+            destination: request.destination.clone(),
+            time,
+            seats: SeatLayout::default(), // Starts with the default, empty layout; the passengers are seated afterwards.
+        }
+    }
+
+    fn layout(&self) -> &SeatLayout {
+        &self.seats
+    }
+
+    fn layout_mut(&mut self) -> &mut SeatLayout {
+        &mut self.seats
+    }
+
+    fn summary(&self) -> EntitySummary {
+        EntitySummary {
+            kind: Self::KIND,
+            id: self.vehicle_id.clone(),
+            name: self.name.clone(),
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            time: self.time,
+        }
+    }
+}
+
+// Trains are booked as the C++ BookingService<Train> did.
+impl Bookable for Train {
+    const KIND: &'static str = "Train";
+
+    fn create(request: &BookingRequest, time: i64) -> Self {
+        Train {
+            train_id: request.entity_id.clone(), // Sets train_id from input, matching C++.
+            name: request.name.clone(),
+            source: request.source.clone(),
+            destination: request.destination.clone(),
+            time,
+            seats: SeatLayout::default(), // Starts with the default, empty layout; the passengers are seated afterwards.
+        }
+    }
+
+    fn layout(&self) -> &SeatLayout {
+        &self.seats
+    }
+
+    fn layout_mut(&mut self) -> &mut SeatLayout {
+        &mut self.seats
+    }
+
+    fn summary(&self) -> EntitySummary {
+        EntitySummary {
+            kind: Self::KIND,
+            id: self.train_id.clone(),
+            name: self.name.clone(),
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            time: self.time,
+        }
+    }
+}
 
 // Defines a BookingService trait, replacing C++'s BookingService template class.
 pub trait BookingService {
-    // Declares a method to book an entity, returning io::Result for error handling.
-    fn book(&self, entity_id: String, user: User, name: String, source: String, destination: String) -> std::io::Result<()>;
-    // Declares a method to cancel a booking (no return, as in C++).
-    fn cancel_booking(&self, entity_id: String, user_id: String);
-    // Declares a method to print booking details (no return, as in C++).
-    fn print_booking(&self, entity_id: String, user_id: String);
+    // Declares a method to book seats for every passenger of the request under one new PNR, all or
+    // nothing, returning the PNR and the seats given. Every call is a separate booking, so a user can
+    // book the same entity more than once.
+    fn book(&self, request: BookingRequest) -> Result<Ticket, BookingError>;
+    // Declares a method to cancel every booking of a user on an entity, returning the seats that were released.
+    fn cancel_booking(&self, entity_id: String, user_id: String) -> Result<Vec<SeatNumber>, BookingError>;
+    // Declares a method to cancel the booking with the given PNR, returning the seats that were released.
+    fn cancel_by_pnr(&self, pnr: String) -> Result<Vec<SeatNumber>, BookingError>;
+    // Declares a method to cancel some passengers of the booking with the given PNR, keeping the
+    // others booked, returning the seats that were released.
+    fn cancel_passengers(&self, pnr: String, user_ids: Vec<String>) -> Result<Vec<SeatNumber>, BookingError>;
+    // Declares a method to look up a user's bookings on an entity as a structured view.
+    fn get_booking(&self, entity_id: String, user_id: String) -> Result<BookingView, BookingError>;
+    // Declares a method to look up the booking with the given PNR as a structured view.
+    fn get_booking_by_pnr(&self, pnr: String) -> Result<BookingView, BookingError>;
+    // Declares a method to report the free seats of each class of an entity.
+    fn availability(&self, entity_id: String) -> Result<Vec<ClassAvailability>, BookingError>;
+
+    // Prints booking details to stdout, rendering the view returned by get_booking with the
+    // Aadhar number masked.
+    fn print_booking(&self, entity_id: String, user_id: String) -> Result<(), BookingError> {
+        let view = self.get_booking(entity_id, user_id)?;
+        print!("{}", view);
+        Ok(())
+    }
+
+    // Prints booking details with the passenger's full Aadhar number, for staff checking documents.
+    fn print_booking_revealed(&self, entity_id: String, user_id: String) -> Result<(), BookingError> {
+        let view = self.get_booking(entity_id, user_id)?;
+        print!("{}", view.revealed());
+        Ok(())
+    }
+
+    // Prints the booking with the given PNR to stdout, with the Aadhar number masked.
+    fn print_booking_by_pnr(&self, pnr: String) -> Result<(), BookingError> {
+        let view = self.get_booking_by_pnr(pnr)?;
+        print!("{}", view);
+        Ok(())
+    }
 }
 
 // Defines a generic BookingServiceImpl struct, similar to C++'s BookingService class.
 pub struct BookingServiceImpl<T> {
-    _phantom: std::marker::PhantomData<T>, // Ensures type safety for generic T.
+    storage: Box<dyn FileIO<Item = T>>, // The storage backend every booking operation reads and writes.
+    users: UserRegistry,                // The registry of passengers the seats refer to.
+    allocator: Box<dyn SeatAllocator>,  // Chooses the seats of each booking.
 }
 
-// Implements BookingServiceImpl for any T, providing a constructor and save_booking.
-impl<T> BookingServiceImpl<T> {
-    // Creates a new BookingServiceImpl instance.
+// Implements BookingServiceImpl for any stored T, providing constructors and save_booking.
+impl<T: Record + 'static> BookingServiceImpl<T> {
+    // Creates a new BookingServiceImpl instance using the default "db.json" in the current directory.
     pub fn new() -> Self {
-        BookingServiceImpl {
-            _phantom: std::marker::PhantomData, // Initializes PhantomData for type T.
-        }
+        Self::with_storage(Box::new(FileIOImpl::<T>::new()), Box::new(FileIOImpl::<User>::new()))
     }
 
-    // Saves an entity using FileIO, equivalent to C++'s saveBooking private method.
-    fn save_booking(&self, entity: &T) -> std::io::Result<()>
-    where
-        FileIOImpl<T>: super::file_io::FileIO<Item = T> // Constrains the FileIO implementation for T to implement FileIO with Item = T.
-    {
-        let file = FileIOImpl::<T>::new(); // Creates a FileIO instance for type T.
-        file.save_to_file(entity) // Delegates to FileIO's save_to_file.
+    // Creates a BookingServiceImpl that uses the given storage backend (JSON, SQLite, ...) for the
+    // trains/vehicles and `users` for the user registry, normally the same backend.
+    pub fn with_storage(storage: Box<dyn FileIO<Item = T>>, users: Box<dyn FileIO<Item = User>>) -> Self {
+        BookingServiceImpl { storage, users: UserRegistry::new(users), allocator: Box::new(KeepTogether) }
+    }
+
+    // Sets the strategy choosing the seats of each booking; KeepTogether unless set.
+    pub fn with_allocator(mut self, allocator: Box<dyn SeatAllocator>) -> Self {
+        self.allocator = allocator;
+        self
     }
-}
 
-// Implements BookingService for Vehicle, equivalent to C++'s BookingService<Vehicle>.
-impl BookingService for BookingServiceImpl<Vehicle> {
-    // Books a Vehicle, equivalent to C++'s book method.
-    fn book(&self, entity_id: String, user: User, name: String, source: String, destination: String) -> std::io::Result<()> {
-        // Creates a new Vehicle with provided details.
-        let vehicle = Vehicle {
-            vehicle_id: entity_id, // Sets vehicle_id from input, matching C++.
-            name,                 // Sets name from input.
-            source,               // Sets source from input.
-            destination,          // Sets destination from input.
-            time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64, // Sets current timestamp, equivalent to time(nullptr).
-            seats: vec![vec![user]], // Initializes seats with one row containing the user, matching C++'s {{user}}.
+    // Returns the user registry, e.g. to rename a user.
+    pub fn users(&self) -> &UserRegistry {
+        &self.users
+    }
+
+    // Runs a booking change to the entity stored under `entity_id` as one read-modify-write cycle
+    // under the database's exclusive lock, equivalent to C++'s saveBooking private method. The stored
+    // record is replaced by id, so booking, cancellation and later edits converge on a single record
+    // per vehicle/train, and concurrent operators can't overwrite each other's seats. `change` gets
+    // the entity (None if it isn't stored yet) and a check for PNRs already held in the collection.
+    fn save_booking<R>(
+        &self,
+        entity_id: &str,
+        mut change: impl FnMut(&mut Option<T>, &dyn Fn(&Pnr) -> bool) -> Result<R, BookingError>,
+    ) -> Result<R, BookingError> {
+        let mut outcome = None;
+        self.storage.update_record(entity_id, &mut |entity, taken| {
+            outcome = Some(change(entity, taken).map_err(BookingError::into_io_error)?);
+            Ok(())
+        })?;
+        Ok(outcome.expect("update_record succeeded, so the change ran"))
+    }
+
+    // Returns the id of the entity holding the booking with the given PNR.
+    fn entity_with_pnr(&self, pnr: &Pnr) -> Result<String, BookingError> {
+        match self.storage.read_record_with_pnr(pnr)? {
+            Some(entity) => Ok(entity.record_id().to_string()),
+            None => Err(BookingError::UnknownPnr { pnr: pnr.to_string() }),
+        }
+    }
+
+    // Takes the passengers a refused booking registered out of the registry again, except any that a
+    // booking made in the meantime has seated. Leaving one behind only keeps an unused registry entry,
+    // so a failure here is not reported over the booking's own error.
+    fn unregister_unseated(&self, added: &[String]) {
+        let Ok(entities) = self.storage.read_from_file() else {
+            return;
         };
-        self.save_booking(&vehicle) // Saves the Vehicle to db.json.
-    }
-
-    // Cancels a booking for a Vehicle by user_id. Reads vehicles from storage,
-    // removes the user from the seats for the matched vehicle and saves the updated vehicle.
-    fn cancel_booking(&self, entity_id: String, user_id: String)
-    where
-        FileIOImpl<Vehicle>: super::file_io::FileIO<Item = Vehicle>,
-    {
-        let file = FileIOImpl::<Vehicle>::new();
-        match file.read_from_file() {
-            Ok(mut vehicles) => {
-                if let Some(vehicle) = vehicles.iter_mut().find(|v| v.vehicle_id == entity_id) {
-                    let mut removed = false;
-                    for row in vehicle.seats.iter_mut() {
-                        let before = row.len();
-                        row.retain(|u| u.user_id != user_id);
-                        if row.len() < before {
-                            removed = true;
-                        }
-                    }
-                    // Remove empty rows
-                    vehicle.seats.retain(|r| !r.is_empty());
-
-                    if removed {
-                        if let Err(e) = self.save_booking(vehicle) {
-                            eprintln!("Failed to save updated booking: {}", e);
-                        } else {
-                            println!("Cancelled booking for user {}", user_id);
-                        }
-                    } else {
-                        println!("No booking found for user {} on vehicle {}", user_id, entity_id);
-                    }
-                } else {
-                    println!("Vehicle with id {} not found", entity_id);
+        let seated = |user_id: &str| entities.iter().any(|e| e.seats().is_some_and(|s| !s.seats_of(user_id).is_empty()));
+        let unseated: Vec<String> = added.iter().filter(|id| !seated(id)).cloned().collect();
+        let _ = self.users.unregister(&unseated);
+    }
+
+    // Completes booked seats with their passengers' details from the registry.
+    fn booked_seats(&self, seats: Vec<(SeatPosition, &Seat)>) -> Result<Vec<BookedSeat>, BookingError> {
+        let mut passengers: Vec<User> = Vec::new();
+        let mut booked = Vec::new();
+        for (position, seat) in seats {
+            let user_id = seat.passenger.as_deref().unwrap_or_default();
+            let passenger = match passengers.iter().find(|u| u.user_id == user_id) {
+                Some(passenger) => passenger.clone(),
+                None => {
+                    let passenger = self.users.get(user_id)?;
+                    passengers.push(passenger.clone());
+                    passenger
                 }
-            }
-            Err(e) => eprintln!("Failed to read vehicles: {}", e),
+            };
+            booked.push(BookedSeat { pnr: seat.pnr.clone(), position, passenger });
         }
+        Ok(booked)
     }
 
-    // Prints booking details for a given vehicle and user.
-    fn print_booking(&self, entity_id: String, user_id: String)
-    where
-        FileIOImpl<Vehicle>: super::file_io::FileIO<Item = Vehicle>,
-    {
-        let file = FileIOImpl::<Vehicle>::new();
-        match file.read_from_file() {
-            Ok(vehicles) => {
-                if let Some(vehicle) = vehicles.iter().find(|v| v.vehicle_id == entity_id) {
-                    println!(
-                        "Vehicle: {} (id: {}), {} -> {}, time: {}",
-                        vehicle.name, vehicle.vehicle_id, vehicle.source, vehicle.destination, vehicle.time
-                    );
-                    let mut found = false;
-                    for (row_i, row) in vehicle.seats.iter().enumerate() {
-                        for (col_i, user) in row.iter().enumerate() {
-                            if user.user_id == user_id {
-                                println!(
-                                    "Found booking - row: {}, col: {}, user: {} (id: {})",
-                                    row_i, col_i, user.name, user.user_id
-                                );
-                                found = true;
-                            }
-                        }
-                    }
-                    if !found {
-                        println!("No booking found for user {} on vehicle {}", user_id, entity_id);
-                    }
-                } else {
-                    println!("Vehicle with id {} not found", entity_id);
-                }
+    // Frees the seats of the booking with the given PNR, wherever it is.
+    fn release_pnr(&self, pnr: String) -> Result<Vec<SeatNumber>, BookingError> {
+        let pnr = parse_pnr(&pnr)?;
+        let entity_id = self.entity_with_pnr(&pnr)?;
+        self.save_booking(&entity_id, |entity, _| {
+            // Rechecked under the lock, since the booking may have been cancelled in the meantime.
+            let released = entity.as_mut().and_then(|e| e.seats_mut()).map(|s| s.release_pnr(&pnr)).unwrap_or_default();
+            if released.is_empty() {
+                return Err(BookingError::UnknownPnr { pnr: pnr.to_string() });
             }
-            Err(e) => eprintln!("Failed to read vehicles: {}", e),
-        }
+            Ok(released)
+        })
     }
-}
 
-// Implements BookingService for Train, equivalent to C++'s BookingService<Train>.
-impl BookingService for BookingServiceImpl<Train> {
-    // Books a Train, equivalent to C++'s book method.
-    fn book(&self, entity_id: String, user: User, name: String, source: String, destination: String) -> std::io::Result<()> {
-        // Creates a new Train with provided details.
-        let train = Train {
-            train_id: entity_id, // Sets train_id from input, matching C++.
-            name,
-            source,
-            destination,
-            time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64, // Sets current timestamp.
-            seats: vec![vec![user]], // Initializes seats with one row containing the user.
-        };
-        self.save_booking(&train) // Saves the Train to db.json.
-    }
-
-    fn cancel_booking(&self, entity_id: String, user_id: String)
-    where
-        FileIOImpl<Train>: super::file_io::FileIO<Item = Train>,
-    {
-        let file = FileIOImpl::<Train>::new();
-        match file.read_from_file() {
-            Ok(mut trains) => {
-                if let Some(train) = trains.iter_mut().find(|t| t.train_id == entity_id) {
-                    let mut removed = false;
-                    for row in train.seats.iter_mut() {
-                        let before = row.len();
-                        row.retain(|u| u.user_id != user_id);
-                        if row.len() < before {
-                            removed = true;
-                        }
-                    }
-                    // Remove empty rows
-                    train.seats.retain(|r| !r.is_empty());
-
-                    if removed {
-                        if let Err(e) = self.save_booking(train) {
-                            eprintln!("Failed to save updated booking: {}", e);
-                        } else {
-                            println!("Cancelled booking for user {}", user_id);
-                        }
-                    } else {
-                        println!("No booking found for user {} on train {}", user_id, entity_id);
-                    }
-                } else {
-                    println!("Train with id {} not found", entity_id);
+    // Frees the seats of the listed passengers of booking `pnr`; if any of them holds no seat under
+    // it, nothing is freed.
+    fn release_passengers(&self, pnr: String, user_ids: Vec<String>) -> Result<Vec<SeatNumber>, BookingError> {
+        let pnr = parse_pnr(&pnr)?;
+        if user_ids.is_empty() {
+            return Err(BookingError::Validation("no passengers to cancel were given".to_string()));
+        }
+        let entity_id = self.entity_with_pnr(&pnr)?;
+        self.save_booking(&entity_id, |entity, _| {
+            let Some(seats) = entity.as_mut().and_then(|e| e.seats_mut()).filter(|s| s.holds_pnr(&pnr)) else {
+                return Err(BookingError::UnknownPnr { pnr: pnr.to_string() });
+            };
+            let mut released = Vec::new();
+            for user_id in &user_ids {
+                let seat = seats.release_passenger(&pnr, user_id);
+                if seat.is_empty() {
+                    // The error discards the whole change, so the seats freed so far stay booked.
+                    return Err(BookingError::NotOnPnr { pnr: pnr.to_string(), user_id: user_id.clone() });
                 }
+                released.extend(seat);
             }
-            Err(e) => eprintln!("Failed to read trains: {}", e),
+            Ok(released)
+        })
+    }
+}
+
+// Implements Default for BookingServiceImpl, delegating to new().
+impl<T: Record + 'static> Default for BookingServiceImpl<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implements BookingService for every bookable entity, equivalent to C++'s BookingService<Vehicle>
+// and BookingService<Train>.
+impl<T: Bookable + 'static> BookingService for BookingServiceImpl<T> {
+    // Books a Train or Vehicle, equivalent to C++'s book method. Reuses the stored entity with the
+    // same id and only creates a new one when it doesn't exist yet.
+    fn book(&self, request: BookingRequest) -> Result<Ticket, BookingError> {
+        request.validate()?;
+        // Registers the passengers before seating them, so no stored seat ever refers to an unknown
+        // user (SQLite enforces this); ids taken by someone else are refused before any seat is given.
+        let added = self.users.register_new(&request.passengers)?;
+        let booked = self.save_booking(&request.entity_id, |entity, taken| {
+            // Draws a PNR no stored booking holds; the lock keeps two bookings from drawing the same one.
+            let pnr = Pnr::generate(taken);
+            let entity = entity.get_or_insert_with(|| {
+                // Creates a new entity with provided details if none is stored under entity_id.
+                let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64; // Equivalent to time(nullptr).
+                T::create(&request, time)
+            });
+            // Refuses the booking when there aren't enough free seats for everyone.
+            seat_passengers(entity.layout_mut(), &request, pnr, self.allocator.as_ref())
+        });
+        if booked.is_err() {
+            self.unregister_unseated(&added);
         }
+        booked
     }
 
-    fn print_booking(&self, entity_id: String, user_id: String)
-    where
-        FileIOImpl<Train>: super::file_io::FileIO<Item = Train>,
-    {
-        let file = FileIOImpl::<Train>::new();
-        match file.read_from_file() {
-            Ok(trains) => {
-                if let Some(train) = trains.iter().find(|t| t.train_id == entity_id) {
-                    println!(
-                        "Train: {} (id: {}), {} -> {}, time: {}",
-                        train.name, train.train_id, train.source, train.destination, train.time
-                    );
-                    let mut found = false;
-                    for (row_i, row) in train.seats.iter().enumerate() {
-                        for (col_i, user) in row.iter().enumerate() {
-                            if user.user_id == user_id {
-                                println!(
-                                    "Found booking - row: {}, col: {}, user: {} (id: {})",
-                                    row_i, col_i, user.name, user.user_id
-                                );
-                                found = true;
-                            }
-                        }
-                    }
-                    if !found {
-                        println!("No booking found for user {} on train {}", user_id, entity_id);
-                    }
-                } else {
-                    println!("Train with id {} not found", entity_id);
-                }
+    // Cancels the bookings of an entity by user_id: frees the user's seats on the matched entity,
+    // whatever their PNR, and saves the updated entity.
+    fn cancel_booking(&self, entity_id: String, user_id: String) -> Result<Vec<SeatNumber>, BookingError> {
+        self.save_booking(&entity_id, |entity, _| {
+            let entity = entity.as_mut().ok_or(BookingError::NotFound { kind: T::KIND, id: entity_id.clone() })?;
+            // Frees the user's seats; the layout itself keeps its shape.
+            let released = entity.layout_mut().release(&user_id);
+            if released.is_empty() {
+                return Err(BookingError::NoSuchPassenger { entity_id: entity_id.clone(), user_id: user_id.clone() });
             }
-            Err(e) => eprintln!("Failed to read trains: {}", e),
+            Ok(released)
+        })
+    }
+
+    // Cancels the booking with the given PNR.
+    fn cancel_by_pnr(&self, pnr: String) -> Result<Vec<SeatNumber>, BookingError> {
+        self.release_pnr(pnr)
+    }
+
+    // Cancels some passengers of the booking with the given PNR.
+    fn cancel_passengers(&self, pnr: String, user_ids: Vec<String>) -> Result<Vec<SeatNumber>, BookingError> {
+        self.release_passengers(pnr, user_ids)
+    }
+
+    // Returns the bookings of a given entity and user as a structured view.
+    fn get_booking(&self, entity_id: String, user_id: String) -> Result<BookingView, BookingError> {
+        let entity = self
+            .storage
+            .read_record(&entity_id)?
+            .ok_or(BookingError::NotFound { kind: T::KIND, id: entity_id.clone() })?;
+        let seats = entity.layout().booked_seats(|seat| seat.passenger.as_deref() == Some(user_id.as_str()));
+        if seats.is_empty() {
+            return Err(BookingError::NoSuchPassenger { entity_id, user_id });
         }
+        // The passenger details come from the registry, so they are the same on every booking.
+        Ok(BookingView { entity: entity.summary(), seats: self.booked_seats(seats)? })
     }
-}
\ No newline at end of file
+
+    // Returns the booking with the given PNR as a structured view.
+    fn get_booking_by_pnr(&self, pnr: String) -> Result<BookingView, BookingError> {
+        let pnr = parse_pnr(&pnr)?;
+        let entity = self.storage.read_record_with_pnr(&pnr)?.ok_or(BookingError::UnknownPnr { pnr: pnr.to_string() })?;
+        let seats = entity.layout().booked_seats(|seat| seat.pnr.as_ref() == Some(&pnr));
+        Ok(BookingView { entity: entity.summary(), seats: self.booked_seats(seats)? })
+    }
+
+    // Returns the free seats of each class of an entity.
+    fn availability(&self, entity_id: String) -> Result<Vec<ClassAvailability>, BookingError> {
+        let entity = self
+            .storage
+            .read_record(&entity_id)?
+            .ok_or(BookingError::NotFound { kind: T::KIND, id: entity_id.clone() })?;
+        Ok(class_availability(entity.layout()))
+    }
+}
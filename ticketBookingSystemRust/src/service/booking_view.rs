@@ -0,0 +1,163 @@
+// Imports Serialize so views can be handed to UIs and reports as JSON.
+use serde::{Serialize, Serializer};
+// Imports fmt for the console renderer.
+use std::fmt;
+// Imports the entities a booking view is built from.
+use super::super::entities::pnr::Pnr;
+use super::super::entities::seat_class::SeatClass;
+use super::super::entities::seat_layout::{SeatKind, SeatNumber, SeatPosition};
+use super::super::entities::user::User;
+
+// Summarises the train or vehicle a booking belongs to.
+#[derive(Serialize, Clone, Debug)]
+pub struct EntitySummary {
+    pub kind: &'static str,  // "Train" or "Vehicle".
+    pub id: String,          // The train/vehicle id.
+    pub name: String,        // The train/vehicle name.
+    pub source: String,      // Starting station.
+    pub destination: String, // Destination station.
+    pub time: i64,           // Unix timestamp stored with the entity.
+}
+
+// The seat given to one passenger of a booking.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SeatAssignment {
+    pub user_id: String,        // The passenger.
+    pub seat: SeatNumber,       // Their seat.
+    pub kind: Option<SeatKind>, // The seat's kind, if known.
+}
+
+// What a successful BookingService::book hands back: the booking's PNR and every passenger's seat.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Ticket {
+    pub pnr: Pnr,                     // PNR to look up, print or cancel the booking with.
+    pub seats: Vec<SeatAssignment>,   // The passengers' seats, in the order of the request.
+    pub class: Option<SeatClass>,     // Class the request asked for, if any.
+    pub preference: Option<SeatKind>, // Kind of seat the request asked for, if any.
+}
+
+impl Ticket {
+    // Lists the passengers who asked for a kind of seat but got another, because too few were free.
+    pub fn unmet_preferences(&self) -> Vec<&SeatAssignment> {
+        match self.preference {
+            Some(kind) => self.seats.iter().filter(|s| s.kind != Some(kind)).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+// Renders the ticket as "PNR 4K7QM2X9HT: S1-1 (u1, window seat), S1-2 (u2, middle seat)", with the
+// class code after the PNR when one was requested ("PNR 4K7QM2X9HT 3A: ...") and followed by
+// "; no window seat left for u2" when the requested kind of seat could not be given to everyone.
+impl fmt::Display for Ticket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seats: Vec<String> = self
+            .seats
+            .iter()
+            .map(|s| match s.kind {
+                Some(kind) => format!("{} ({}, {})", s.seat, s.user_id, kind),
+                None => format!("{} ({})", s.seat, s.user_id),
+            })
+            .collect();
+        match self.class {
+            Some(class) => write!(f, "PNR {} {}: {}", self.pnr, class.code(), seats.join(", "))?,
+            None => write!(f, "PNR {}: {}", self.pnr, seats.join(", "))?,
+        }
+        let unmet: Vec<&str> = self.unmet_preferences().iter().map(|s| s.user_id.as_str()).collect();
+        if let (Some(kind), false) = (self.preference, unmet.is_empty()) {
+            write!(f, "; no {} left for {}", kind, unmet.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Seats of one class on a train or vehicle, as returned by BookingService::availability.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClassAvailability {
+    pub class: SeatClass,     // The class.
+    pub coaches: Vec<String>, // Codes of the class's coaches, front to back.
+    pub capacity: usize,      // Seats in those coaches.
+    pub available: usize,     // Seats still free.
+}
+
+// Renders the availability as "AC 3 Tier (3A), coaches B1, B2: 120 of 128 seats free".
+impl fmt::Display for ClassAvailability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, coaches {}: {} of {} seats free", self.class, self.coaches.join(", "), self.available, self.capacity)
+    }
+}
+
+// One booked seat of a booking view. Serialized with the passenger's Aadhar number masked, as it is
+// printed; only BookingView::revealed shows the full number.
+#[derive(Serialize, Clone, Debug)]
+pub struct BookedSeat {
+    pub pnr: Option<Pnr>,       // PNR of the booking holding the seat.
+    pub position: SeatPosition, // Where the seat is.
+    #[serde(serialize_with = "serialize_masked")]
+    pub passenger: User,        // The passenger seated there, from the user registry.
+}
+
+// A passenger as a serialized view shows them: the User fields, with the Aadhar number masked.
+#[derive(Serialize)]
+struct MaskedPassenger<'a> {
+    #[serde(rename = "userId")]
+    user_id: &'a str,
+    name: &'a str,
+    #[serde(rename = "aadharCard")]
+    aadhar_card: String,
+}
+
+// Serializes a booked seat's passenger as a MaskedPassenger.
+fn serialize_masked<S: Serializer>(user: &User, serializer: S) -> Result<S::Ok, S::Error> {
+    MaskedPassenger { user_id: &user.user_id, name: &user.name, aadhar_card: user.masked_aadhar() }.serialize(serializer)
+}
+
+// Bookings on one train or vehicle, as returned by BookingService::get_booking (a user's seats) and
+// get_booking_by_pnr (the seats of one PNR).
+#[derive(Serialize, Clone, Debug)]
+pub struct BookingView {
+    pub entity: EntitySummary,  // What was booked.
+    pub seats: Vec<BookedSeat>, // The booked seats, in layout order.
+}
+
+impl BookingView {
+    // Renders the booking with the passengers' full Aadhar numbers; Display and Serialize mask them.
+    pub fn revealed(&self) -> RevealedBooking<'_> {
+        RevealedBooking(self)
+    }
+
+    // Writes the console format, with the Aadhar numbers masked unless `reveal` is set.
+    fn render(&self, f: &mut fmt::Formatter<'_>, reveal: bool) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} (id: {}), {} -> {}, time: {}",
+            self.entity.kind, self.entity.name, self.entity.id, self.entity.source, self.entity.destination, self.entity.time
+        )?;
+        for seat in &self.seats {
+            let pnr = seat.pnr.as_ref().map_or("-", Pnr::as_str);
+            write!(f, "Found booking - PNR: {}, seat: {}, user: ", pnr, seat.position.number)?;
+            if reveal {
+                writeln!(f, "{}", seat.passenger.revealed())?;
+            } else {
+                writeln!(f, "{}", seat.passenger)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Renders a booking in the console format used by print_booking, with the Aadhar numbers masked.
+impl fmt::Display for BookingView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, false)
+    }
+}
+
+// A booking rendered with the passengers' full Aadhar numbers, returned by BookingView::revealed.
+pub struct RevealedBooking<'a>(&'a BookingView);
+
+impl fmt::Display for RevealedBooking<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.render(f, true)
+    }
+}
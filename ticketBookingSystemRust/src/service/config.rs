@@ -0,0 +1,220 @@
+// Imports env for the environment variable overrides.
+use std::env;
+// Imports fs to read the config file.
+use std::fs;
+// Imports io for error reporting.
+use std::io;
+// Imports Path and PathBuf for config and database locations.
+use std::path::{Path, PathBuf};
+// Imports Duration for the lock timeout setting.
+use std::time::Duration;
+// Imports the storage backends and the defaults used when nothing is configured.
+use super::file_io::{FileIO, FileIOImpl, Record, DEFAULT_DB_PATH, DEFAULT_LOCK_TIMEOUT};
+use super::sqlite_io::{SqliteIOImpl, DEFAULT_SQLITE_PATH};
+use super::journal_io::{JournalIOImpl, DEFAULT_JOURNAL_PATH};
+use super::encryption::{AadharCipher, EncryptedIO};
+
+// Environment variable holding the database path; overrides the config file.
+pub const DB_PATH_ENV: &str = "TRAIN_BOOKING_DB";
+// Environment variable holding the config file path; overrides the default config location.
+pub const CONFIG_PATH_ENV: &str = "TRAIN_BOOKING_CONFIG";
+// Environment variable holding the path of the Aadhar encryption key file; overrides the config file.
+pub const KEY_FILE_ENV: &str = "TRAIN_BOOKING_KEY_FILE";
+// Environment variable holding the Aadhar encryption key itself; takes precedence over any key file.
+pub const KEY_ENV: &str = "TRAIN_BOOKING_KEY";
+// Environment variable selecting the storage backend ("json", "sqlite" or "journal"); overrides the config file.
+pub const BACKEND_ENV: &str = "TRAIN_BOOKING_BACKEND";
+
+// The storage backends a StorageConfig can select.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Json,   // A single db.json file (FileIOImpl).
+    Sqlite, // An SQLite database file (SqliteIOImpl).
+    Journal, // An append-only event journal with snapshots (JournalIOImpl).
+}
+
+impl Backend {
+    // Parses a backend name as written in the config file or environment.
+    pub fn parse(name: &str) -> Option<Backend> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Backend::Json),
+            "sqlite" => Some(Backend::Sqlite),
+            "journal" => Some(Backend::Journal),
+            _ => None,
+        }
+    }
+
+    // Returns the name used for the backend in the config file and backup manifests.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Json => "json",
+            Backend::Sqlite => "sqlite",
+            Backend::Journal => "journal",
+        }
+    }
+}
+
+// Where and how the booking database is stored.
+//
+// Settings are resolved in this order, later ones winning:
+//   1. built-in defaults (JSON backend, "db.json" in the current directory, or "db.sqlite3" and
+//      "db.journal" for the SQLite and journal backends, 5 second lock timeout),
+//   2. the config file ($TRAIN_BOOKING_CONFIG, or $XDG_CONFIG_HOME/train_booking/config,
+//      or ~/.config/train_booking/config),
+//   3. the TRAIN_BOOKING_BACKEND and TRAIN_BOOKING_DB environment variables.
+//
+// The config file holds `key = value` lines; `#` starts a comment:
+//   backend = sqlite
+//   db_path = /var/lib/train_booking/db.sqlite3
+//   lock_timeout_ms = 10000
+//   backup_dir = /var/backups/train_booking
+//   key_file = /etc/train_booking/aadhar.key
+// Relative paths are resolved against the directory of the config file.
+#[derive(Clone, Debug)]
+pub struct StorageConfig {
+    pub backend: Backend,         // Which storage backend to use.
+    pub db_path: Option<PathBuf>, // Path of the database file; None uses the backend's default.
+    pub lock_timeout: Duration,   // How long to wait for another process's lock.
+    pub backup_dir: Option<PathBuf>, // Where backups go; None uses "backups" next to the database.
+    pub key_file: Option<PathBuf>,   // Key encrypting Aadhar numbers at rest; None stores them as given.
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            backend: Backend::Json,
+            db_path: None,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            backup_dir: None,
+            key_file: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    // Resolves the configuration from the config file and environment, as described above.
+    // A config file named by TRAIN_BOOKING_CONFIG must exist; the default location is optional.
+    pub fn load() -> io::Result<Self> {
+        let mut config = match env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => StorageConfig::from_file(Path::new(&path))?,
+            None => match default_config_path() {
+                Some(path) if path.is_file() => StorageConfig::from_file(&path)?,
+                _ => StorageConfig::default(),
+            },
+        };
+        if let Some(name) = env::var(BACKEND_ENV).ok().filter(|b| !b.is_empty()) {
+            config.backend = Backend::parse(&name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} must be `json`, `sqlite` or `journal`, found `{}`", BACKEND_ENV, name),
+                )
+            })?;
+        }
+        if let Some(key_file) = env::var_os(KEY_FILE_ENV).filter(|p| !p.is_empty()) {
+            config.key_file = Some(PathBuf::from(key_file));
+        }
+        if let Some(db_path) = env::var_os(DB_PATH_ENV).filter(|p| !p.is_empty()) {
+            config.db_path = Some(PathBuf::from(db_path));
+        }
+        Ok(config)
+    }
+
+    // Returns the configured database path, or the default file of the selected backend.
+    pub fn database_path(&self) -> PathBuf {
+        match (&self.db_path, self.backend) {
+            (Some(path), _) => path.clone(),
+            (None, Backend::Json) => PathBuf::from(DEFAULT_DB_PATH),
+            (None, Backend::Sqlite) => PathBuf::from(DEFAULT_SQLITE_PATH),
+            (None, Backend::Journal) => PathBuf::from(DEFAULT_JOURNAL_PATH),
+        }
+    }
+
+    // Returns the configured backup directory, or "backups" in the database's directory.
+    pub fn backups_path(&self) -> PathBuf {
+        match &self.backup_dir {
+            Some(dir) => dir.clone(),
+            None => self.database_path().parent().unwrap_or(Path::new("")).join("backups"),
+        }
+    }
+
+    // Loads the Aadhar encryption key from TRAIN_BOOKING_KEY or the key file, if one is configured.
+    // The key itself is read from the environment here rather than kept in the config, so it never
+    // ends up in debug output.
+    pub fn cipher(&self) -> io::Result<Option<AadharCipher>> {
+        if let Some(key) = env::var(KEY_ENV).ok().filter(|k| !k.is_empty()) {
+            return AadharCipher::from_text(&key).map(Some);
+        }
+        match &self.key_file {
+            Some(path) => AadharCipher::from_key_file(path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Opens the storage for T with the selected backend, encrypting Aadhar numbers when a key is configured.
+    pub fn open<T: Record + 'static>(&self) -> io::Result<Box<dyn FileIO<Item = T>>> {
+        let storage = self.open_unencrypted::<T>();
+        Ok(match self.cipher()? {
+            Some(cipher) => Box::new(EncryptedIO::new(storage, cipher)),
+            None => storage,
+        })
+    }
+
+    // Opens the storage for T with the selected backend, returning stored values as they are.
+    pub fn open_unencrypted<T: Record + 'static>(&self) -> Box<dyn FileIO<Item = T>> {
+        match self.backend {
+            Backend::Json => Box::new(FileIOImpl::<T>::from_config(self)),
+            Backend::Sqlite => Box::new(SqliteIOImpl::<T>::from_config(self)),
+            Backend::Journal => Box::new(JournalIOImpl::<T>::from_config(self)),
+        }
+    }
+
+    // Reads a config file on top of the defaults.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let mut config = StorageConfig::default();
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}:{}: {}", path.display(), index + 1, message),
+                )
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected `key = value`, found `{}`", line)))?;
+            let value = value.trim();
+            match key.trim() {
+                "backend" => {
+                    config.backend = Backend::parse(value)
+                        .ok_or_else(|| invalid(format!("backend must be `json`, `sqlite` or `journal`, found `{}`", value)))?;
+                }
+                "db_path" => config.db_path = Some(base_dir.join(value)),
+                "backup_dir" => config.backup_dir = Some(base_dir.join(value)),
+                "key_file" => config.key_file = Some(base_dir.join(value)),
+                "lock_timeout_ms" => {
+                    let millis: u64 = value
+                        .parse()
+                        .map_err(|_| invalid(format!("lock_timeout_ms must be a number of milliseconds, found `{}`", value)))?;
+                    config.lock_timeout = Duration::from_millis(millis);
+                }
+                other => return Err(invalid(format!("unknown setting `{}`", other))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Returns the per-user config file location: $XDG_CONFIG_HOME/train_booking/config, falling back
+// to ~/.config/train_booking/config.
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("train_booking").join("config"))
+}
@@ -0,0 +1,270 @@
+// Imports HashMap to reuse ciphertexts of unchanged values.
+use std::collections::HashMap;
+// Imports fs and io for key files and errors.
+use std::fs;
+use std::io;
+// Imports Path for key file and backup locations.
+use std::path::Path;
+// Imports AES-256-GCM, the authenticated cipher used for stored Aadhar numbers.
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+// Imports base64 to store ciphertexts and keys as text.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+// Imports SHA-256 to derive key ids.
+use sha2::{Digest, Sha256};
+// Imports AadharNumber to rewrap decrypted and encrypted values, and the prefix marking encrypted ones.
+use super::super::entities::aadhar::{AadharNumber, ENCRYPTED_PREFIX};
+// Imports the PNR that bookings are looked up by.
+use super::super::entities::pnr::Pnr;
+// Imports the storage trait that EncryptedIO wraps.
+use super::file_io::{FileIO, Record, RecordChange};
+
+// Length in bytes of an AES-256 key.
+pub const KEY_LEN: usize = 32;
+// Length in bytes of an AES-GCM nonce.
+const NONCE_LEN: usize = 12;
+
+// Encrypts and decrypts Aadhar numbers with AES-256-GCM. Each value names the key it was encrypted
+// with, so a value written under another key is reported as such instead of failing to decrypt.
+pub struct AadharCipher {
+    key_id: String,     // Short id of the key, stored with every value.
+    cipher: Aes256Gcm,  // The AES-256-GCM cipher for the key.
+}
+
+impl AadharCipher {
+    // Creates a cipher from raw key bytes.
+    pub fn new(key: &[u8; KEY_LEN]) -> Self {
+        AadharCipher { key_id: key_id(key), cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)) }
+    }
+
+    // Parses a key written as 64 hex digits or as base64 of 32 bytes.
+    pub fn from_text(text: &str) -> io::Result<Self> {
+        let text = text.trim();
+        let bytes = if text.len() == KEY_LEN * 2 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+            (0..KEY_LEN).map(|i| u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).unwrap()).collect()
+        } else {
+            BASE64.decode(text).unwrap_or_default()
+        };
+        let key: [u8; KEY_LEN] = bytes.try_into().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "encryption key must be 32 bytes, written as 64 hex digits or base64")
+        })?;
+        Ok(AadharCipher::new(&key))
+    }
+
+    // Reads a key file holding the key as text.
+    pub fn from_key_file(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot read key file {}: {}", path.display(), e)))?;
+        Self::from_text(&text)
+    }
+
+    // Returns the id of the key: the first 8 hex digits of its SHA-256.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    // Returns true if `value` was encrypted with this key.
+    pub fn encrypted_this_key(&self, value: &str) -> bool {
+        value.strip_prefix(ENCRYPTED_PREFIX).and_then(|rest| rest.split_once(':')).is_some_and(|(id, _)| id == self.key_id)
+    }
+
+    // Encrypts a value with a fresh random nonce.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext.as_bytes()).expect("AES-GCM encryption of a short value cannot fail");
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        format!("{}{}:{}", ENCRYPTED_PREFIX, self.key_id, BASE64.encode(payload))
+    }
+
+    // Decrypts a value written by encrypt; plaintext values (stored before encryption was enabled)
+    // are returned unchanged.
+    pub fn decrypt(&self, value: &str) -> io::Result<String> {
+        let Some(rest) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let (id, payload) = rest.split_once(':').ok_or_else(|| invalid("malformed encrypted value".to_string()))?;
+        if id != self.key_id {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("value was encrypted with key {}, not the configured key {}", id, self.key_id),
+            ));
+        }
+        let payload = BASE64.decode(payload).map_err(|e| invalid(format!("malformed encrypted value: {}", e)))?;
+        if payload.len() < NONCE_LEN {
+            return Err(invalid("malformed encrypted value: too short".to_string()));
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid(format!("value encrypted with key {} failed authentication", id)))?;
+        String::from_utf8(plaintext).map_err(|_| invalid("decrypted value is not text".to_string()))
+    }
+}
+
+// Derives the id stored with each value from the key bytes.
+fn key_id(key: &[u8]) -> String {
+    Sha256::digest(key).iter().take(4).map(|b| format!("{:02x}", b)).collect()
+}
+
+// Writes a new random key to `path` as base64, readable only by the owner on Unix. Refuses to
+// overwrite an existing file, since losing a key makes its values unreadable.
+pub fn generate_key_file(path: &Path) -> io::Result<AadharCipher> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    io::Write::write_all(&mut options.open(path)?, format!("{}\n", BASE64.encode(key)).as_bytes())?;
+    Ok(AadharCipher::new(&key.into()))
+}
+
+// Wraps any storage backend so that Aadhar numbers are encrypted before they are stored and
+// decrypted when read. Callers without the key use the backend directly and only see ciphertexts.
+pub struct EncryptedIO<T> {
+    inner: Box<dyn FileIO<Item = T>>, // The backend actually storing the entities.
+    cipher: AadharCipher,             // Encrypts new values and decrypts stored ones.
+}
+
+impl<T> EncryptedIO<T> {
+    // Wraps `inner`, encrypting with `cipher`.
+    pub fn new(inner: Box<dyn FileIO<Item = T>>, cipher: AadharCipher) -> Self {
+        EncryptedIO { inner, cipher }
+    }
+}
+
+// Decrypts every Aadhar number held by the records, remembering each ciphertext so unchanged values
+// can be written back as they were instead of being re-encrypted.
+fn decrypt_all<T: Record>(cipher: &AadharCipher, records: &mut [T], seen: &mut HashMap<(String, String), String>) -> io::Result<()> {
+    for record in records.iter_mut() {
+        let id = record.record_id().to_string();
+        for aadhar in record.aadhar_numbers_mut() {
+            if aadhar.is_encrypted() {
+                let plaintext = cipher.decrypt(aadhar.reveal())?;
+                let ciphertext = std::mem::replace(aadhar, AadharNumber::from_stored(plaintext.clone()));
+                seen.insert((id.clone(), plaintext), ciphertext.reveal().to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+// Encrypts every Aadhar number held by the records, reusing the stored ciphertext of unchanged values.
+fn encrypt_all<T: Record>(cipher: &AadharCipher, records: &mut [T], seen: &HashMap<(String, String), String>) {
+    for record in records.iter_mut() {
+        let id = record.record_id().to_string();
+        for aadhar in record.aadhar_numbers_mut() {
+            if !aadhar.is_encrypted() {
+                *aadhar = match seen.get(&(id.clone(), aadhar.reveal().to_string())) {
+                    Some(ciphertext) => AadharNumber::from_stored(ciphertext.as_str()),
+                    None => AadharNumber::from_stored(cipher.encrypt(aadhar.reveal())),
+                };
+            }
+        }
+    }
+}
+
+// Implements FileIO by delegating to the wrapped backend.
+impl<T: Record> FileIO for EncryptedIO<T> {
+    type Item = T;
+
+    // Saves an entity with its Aadhar numbers encrypted.
+    fn save_to_file(&self, entity: &T) -> io::Result<()> {
+        self.update_in_file(&mut |entities| {
+            match entities.iter_mut().find(|e| e.record_id() == entity.record_id()) {
+                Some(existing) => *existing = entity.clone(),
+                None => entities.push(entity.clone()),
+            }
+            Ok(())
+        })
+    }
+
+    // Reads the entities with their Aadhar numbers decrypted.
+    fn read_from_file(&self) -> io::Result<Vec<T>> {
+        let mut entities = self.inner.read_from_file()?;
+        decrypt_all(&self.cipher, &mut entities, &mut HashMap::new())?;
+        Ok(entities)
+    }
+
+    // Deletes the entity with the given id.
+    fn delete_from_file(&self, id: &str) -> io::Result<bool> {
+        self.inner.delete_from_file(id)
+    }
+
+    // Runs `apply` on decrypted entities and encrypts the result before it is stored.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<T>) -> io::Result<()>) -> io::Result<()> {
+        self.inner.update_in_file(&mut |entities| {
+            let mut seen = HashMap::new();
+            decrypt_all(&self.cipher, entities, &mut seen)?;
+            apply(entities)?;
+            encrypt_all(&self.cipher, entities, &seen);
+            Ok(())
+        })
+    }
+
+    // Reads one entity with its Aadhar numbers decrypted.
+    fn read_record(&self, id: &str) -> io::Result<Option<T>> {
+        let mut entities: Vec<T> = self.inner.read_record(id)?.into_iter().collect();
+        decrypt_all(&self.cipher, &mut entities, &mut HashMap::new())?;
+        Ok(entities.pop())
+    }
+
+    // Reads the entity holding the PNR with its Aadhar numbers decrypted.
+    fn read_record_with_pnr(&self, pnr: &Pnr) -> io::Result<Option<T>> {
+        let mut entities: Vec<T> = self.inner.read_record_with_pnr(pnr)?.into_iter().collect();
+        decrypt_all(&self.cipher, &mut entities, &mut HashMap::new())?;
+        Ok(entities.pop())
+    }
+
+    // Runs `apply` on the decrypted entity and encrypts the result before it is stored.
+    fn update_record(&self, id: &str, apply: &mut RecordChange<T>) -> io::Result<()> {
+        self.inner.update_record(id, &mut |entity, taken| {
+            let mut seen = HashMap::new();
+            decrypt_all(&self.cipher, entity.as_mut_slice(), &mut seen)?;
+            apply(entity, taken)?;
+            encrypt_all(&self.cipher, entity.as_mut_slice(), &seen);
+            Ok(())
+        })
+    }
+
+    // Backups hold the stored, encrypted values.
+    fn backup_to(&self, dir: &Path) -> io::Result<Vec<String>> {
+        self.inner.backup_to(dir)
+    }
+
+    // Restores the stored, encrypted values.
+    fn restore_from(&self, dir: &Path) -> io::Result<()> {
+        self.inner.restore_from(dir)
+    }
+}
+
+// Re-encrypts every Aadhar number stored by `storage` under `new`: values written under `old` and
+// plaintext values are rewritten, values already under `new` are kept, so an interrupted rotation can
+// simply be run again. Without `old`, only plaintext values can be encrypted. Returns the number of
+// values rewritten.
+pub fn rotate_key<T: Record>(storage: &dyn FileIO<Item = T>, old: Option<&AadharCipher>, new: &AadharCipher) -> io::Result<usize> {
+    let mut rewritten = 0;
+    storage.update_in_file(&mut |records| {
+        rewritten = 0;
+        for record in records.iter_mut() {
+            for aadhar in record.aadhar_numbers_mut() {
+                if !new.encrypted_this_key(aadhar.reveal()) {
+                    let plaintext = match old {
+                        Some(old) => old.decrypt(aadhar.reveal())?,
+                        None if aadhar.is_encrypted() => {
+                            return Err(io::Error::new(io::ErrorKind::InvalidInput, "stored values are encrypted, but no current key is configured"));
+                        }
+                        None => aadhar.reveal().to_string(),
+                    };
+                    *aadhar = AadharNumber::from_stored(new.encrypt(&plaintext));
+                    rewritten += 1;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(rewritten)
+}
@@ -1,238 +1,614 @@
 // Imports File and OpenOptions for file operations, equivalent to C++'s std::fstream.
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 // Imports Read and Write for file I/O, equivalent to C++'s file stream operations.
 use std::io::{Read, Write};
+// Imports fmt for the CorruptDatabase error message.
+use std::fmt;
+// Imports Path and PathBuf for the database location and the files kept next to it.
+use std::path::{Path, PathBuf};
+// Imports time types to timestamp quarantined files and bound lock waits.
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+// Imports thread to sleep between lock attempts.
+use std::thread;
+// Imports Serde traits, since every stored entity is mapped to JSON by Serde.
+use serde::{de::DeserializeOwned, Serialize};
 // Imports serde_json for JSON handling, equivalent to C++'s nlohmann::json.
 use serde_json::{Value, json};
 // Imports Vehicle, Train, and User structs from the entities module.
 use super::super::entities::vehicle::Vehicle;
 use super::super::entities::train::Train;
 use super::super::entities::user::User;
+use super::super::entities::aadhar::AadharNumber;
+use super::super::entities::seat_layout::SeatLayout;
+use super::super::entities::pnr::Pnr;
+// Imports the storage configuration used by from_config.
+use super::config::StorageConfig;
+
+// Declares the schema versions of db.json and the steps that upgrade older files.
+pub mod migrations;
+use migrations::MigrationReport;
+
+// The change FileIO::update_record applies: it gets the record, if stored, and a check telling
+// whether a PNR is held by any booking of the collection.
+pub type RecordChange<'a, T> = dyn FnMut(&mut Option<T>, &dyn Fn(&Pnr) -> bool) -> std::io::Result<()> + 'a;
 
 // Defines a FileIO trait to abstract file operations, replacing C++'s FileIO template class.
 pub trait FileIO {
-    type Item;
-    // Declares a method to save an entity to a file, returning io::Result for error handling.
+    type Item: Record;
+    // Declares a method to save an entity to a file, replacing any stored record with the same id.
     fn save_to_file(&self, entity: &Self::Item) -> std::io::Result<()>;
     // Declares a method to read entities from a file, returning a vector wrapped in io::Result.
     fn read_from_file(&self) -> std::io::Result<Vec<Self::Item>>;
+    // Declares a method to delete the record with the given id, returning whether one was removed.
+    fn delete_from_file(&self, id: &str) -> std::io::Result<bool>;
+    // Declares a read-modify-write cycle: `apply` receives every stored entity and may change, add or
+    // remove them; the result is written back. Nothing is written if `apply` returns an error.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<Self::Item>) -> std::io::Result<()>) -> std::io::Result<()>;
+    // Declares a method to read the record with the given id, None if there is none. The default reads
+    // every record; backends that can look one up, like SQLite, read only that one.
+    fn read_record(&self, id: &str) -> std::io::Result<Option<Self::Item>> {
+        Ok(self.read_from_file()?.into_iter().find(|r| r.record_id() == id))
+    }
+    // Declares a method to read the record holding a booking with the given PNR, None if there is none.
+    fn read_record_with_pnr(&self, pnr: &Pnr) -> std::io::Result<Option<Self::Item>> {
+        Ok(self.read_from_file()?.into_iter().find(|r| r.seats().is_some_and(|s| s.holds_pnr(pnr))))
+    }
+    // Declares a read-modify-write cycle on the record with the given id, like update_in_file: `apply`
+    // receives the record (None if there is none; setting it stores a new one, clearing it deletes it)
+    // and a check telling whether a PNR is held anywhere in the collection, so a new booking can draw
+    // an unused one under the same lock.
+    fn update_record(&self, id: &str, apply: &mut RecordChange<Self::Item>) -> std::io::Result<()> {
+        self.update_in_file(&mut |records| {
+            let index = records.iter().position(|r| r.record_id() == id);
+            let mut record = index.map(|i| records[i].clone());
+            let taken = |pnr: &Pnr| records.iter().any(|r| r.seats().is_some_and(|s| s.holds_pnr(pnr)));
+            apply(&mut record, &taken)?;
+            match (index, record) {
+                (Some(i), Some(record)) => records[i] = record,
+                (Some(i), None) => {
+                    records.remove(i);
+                }
+                (None, Some(record)) => records.push(record),
+                (None, None) => {}
+            }
+            Ok(())
+        })
+    }
+    // Declares a method to copy the whole store (every collection, not just Item's) into the existing
+    // directory `dir` while holding the store's lock, returning the names of the files written.
+    fn backup_to(&self, dir: &Path) -> std::io::Result<Vec<String>>;
+    // Declares a method to replace the whole store with a copy written by backup_to.
+    fn restore_from(&self, dir: &Path) -> std::io::Result<()>;
+}
+
+// An entity that can be stored by FileIOImpl: Serde maps it to JSON and it lives in its own
+// collection of db.json, identified by record_id.
+pub trait Record: Serialize + DeserializeOwned + Clone {
+    // Name of the db.json collection holding this entity, e.g. "trains".
+    const COLLECTION: &'static str;
+    // Name of the id field in the entity's JSON form, e.g. "trainId".
+    const ID_KEY: &'static str;
+    // The id that save_to_file and delete_from_file match records on.
+    fn record_id(&self) -> &str;
+    // The seat layout of the entity, for backends that store seats separately from the entity;
+    // None for records without seats, such as users.
+    fn seats(&self) -> Option<&SeatLayout> {
+        None
+    }
+    fn seats_mut(&mut self) -> Option<&mut SeatLayout> {
+        None
+    }
+    // The Aadhar numbers held by the record, which EncryptedIO encrypts at rest.
+    fn aadhar_numbers_mut(&mut self) -> Vec<&mut AadharNumber> {
+        Vec::new()
+    }
+}
+
+// Trains are stored in the "trains" collection, keyed by trainId.
+impl Record for Train {
+    const COLLECTION: &'static str = TRAINS;
+    const ID_KEY: &'static str = "trainId";
+    fn record_id(&self) -> &str {
+        &self.train_id
+    }
+    fn seats(&self) -> Option<&SeatLayout> {
+        Some(&self.seats)
+    }
+    fn seats_mut(&mut self) -> Option<&mut SeatLayout> {
+        Some(&mut self.seats)
+    }
+}
+
+// Vehicles are stored in the "vehicles" collection, keyed by vehicleId.
+impl Record for Vehicle {
+    const COLLECTION: &'static str = VEHICLES;
+    const ID_KEY: &'static str = "vehicleId";
+    fn record_id(&self) -> &str {
+        &self.vehicle_id
+    }
+    fn seats(&self) -> Option<&SeatLayout> {
+        Some(&self.seats)
+    }
+    fn seats_mut(&mut self) -> Option<&mut SeatLayout> {
+        Some(&mut self.seats)
+    }
+}
+
+// Users are stored once in the "users" registry, keyed by userId; seats refer to them by id.
+impl Record for User {
+    const COLLECTION: &'static str = USERS;
+    const ID_KEY: &'static str = "userId";
+    fn record_id(&self) -> &str {
+        &self.user_id
+    }
+    fn aadhar_numbers_mut(&mut self) -> Vec<&mut AadharNumber> {
+        vec![&mut self.aadhar_card]
+    }
+}
+
+// Describes why db.json could not be parsed; carried inside an io::Error of kind InvalidData
+// so reads and writes refuse to continue instead of treating the file as empty.
+#[derive(Debug)]
+pub struct CorruptDatabase {
+    pub path: String,    // The file that failed to parse.
+    pub line: usize,     // 1-based line of the parse error (0 if the JSON was valid but not an array).
+    pub column: usize,   // 1-based column of the parse error.
+    pub message: String, // The parser's description of the problem.
+}
+
+impl fmt::Display for CorruptDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is corrupt at line {}, column {}: {} (run `main recover` to quarantine it and salvage readable records)",
+            self.path, self.line, self.column, self.message
+        )
+    }
 }
 
+impl std::error::Error for CorruptDatabase {}
+
+// Summarises a recovery run: where the bad file was moved and how many records survived.
+#[derive(Debug)]
+pub struct RecoveryReport {
+    pub quarantined_to: Option<String>, // Path of the quarantined original, None if the file was healthy.
+    pub salvaged: usize,                // Records that parsed and were written back.
+    pub discarded: usize,               // Records that could not be parsed and were dropped.
+}
+
+// Names of the typed collections in db.json; each FileIOImpl only reads and writes its own.
+pub const TRAINS: &str = "trains";
+pub const VEHICLES: &str = "vehicles";
+pub const USERS: &str = "users";
+// Holds records of the old flat layout that were neither trains nor vehicles, so migration loses nothing.
+pub const UNCLASSIFIED: &str = "unclassified";
+const COLLECTIONS: [&str; 4] = [TRAINS, VEHICLES, USERS, UNCLASSIFIED];
+
+// Name of the database copy inside a backup directory.
+const BACKUP_FILE: &str = "db.json";
+
+// Database path used when nothing else is configured, relative to the current directory.
+pub const DEFAULT_DB_PATH: &str = "db.json";
+// How long a FileIOImpl waits for another process to release the database lock by default.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+// How long to sleep between attempts to take a contended lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
 // Defines a generic FileIOImpl struct to hold file I/O configuration, similar to C++'s FileIO class.
 pub struct FileIOImpl<T> {
-    filename: String,                  // Stores the filename ("db.json"), equivalent to C++'s const string filename.
+    path: PathBuf,                     // Stores the database path ("db.json" by default), equivalent to C++'s const string filename.
+    lock_timeout: Duration,            // Stores how long to wait for the advisory lock before giving up.
     _phantom: std::marker::PhantomData<T>, // PhantomData ensures type safety for the generic T, unused at runtime.
 }
 
 // Implements FileIOImpl for any T, providing a constructor.
 impl<T> FileIOImpl<T> {
-    // Creates a new FileIOImpl instance with default filename "db.json".
+    // Creates a new FileIOImpl instance with default filename "db.json" in the current directory.
     pub fn new() -> Self {
+        Self::with_path(DEFAULT_DB_PATH) // Matches C++'s const string filename = "db.json".
+    }
+
+    // Creates a FileIOImpl storing its data at `path`.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
         FileIOImpl {
-            filename: "db.json".to_string(), // Sets the filename, matching C++'s const string filename = "db.json".
+            path: path.into(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT, // Waits up to 5 seconds for other operators by default.
             _phantom: std::marker::PhantomData, // Initializes PhantomData for type T.
         }
     }
-}
-// Implements FileIO trait for FileIOImpl<Vehicle>, equivalent to C++'s FileIO<Vehicle> specialization.
-impl FileIO for FileIOImpl<Vehicle> {
-    type Item = Vehicle;
-
-    // Saves a Vehicle to db.json, equivalent to C++'s FileIO<Vehicle>::saveToFile.
-    fn save_to_file(&self, entity: &Vehicle) -> std::io::Result<()> {
-        // Initializes an empty JSON array to store data, equivalent to C++'s json j.
-        let mut j = json!([]);
-        // Attempts to open and read existing JSON data from the file.
-        if let Ok(mut file) = File::open(&self.filename) {
-            let mut contents = String::new(); // Buffer to store file contents.
-            file.read_to_string(&mut contents)?; // Reads file into string, propagates errors.
-            // Parses file contents as JSON, falling back to empty array if invalid.
-            j = serde_json::from_str(&contents).unwrap_or(json!([]));
-        }
 
-        // Converts seats (Vec<Vec<User>>) to JSON format, matching C++'s seats serialization.
-        let seats_json: Vec<Vec<serde_json::Value>> = entity.seats.iter().map(|row| {
-            row.iter().map(|user| {
-                // Creates a JSON object for each User, matching C++'s userJson structure.
-                json!({
-                    "userId": user.user_id,      // Serializes user_id field.
-                    "name": user.name,           // Serializes name field.
-                    "aadharCard": user.aadhar_card, // Serializes aadhar_card field.
-                })
-            }).collect() // Collects user JSON objects into a row array.
-        }).collect(); // Collects row arrays into seats array.
-
-        // Creates a JSON object for the Vehicle, matching C++'s entityJson.
-        let entity_json = json!({
-            "vehicleId": entity.vehicle_id,   // Serializes vehicle_id.
-            "name": entity.name,             // Serializes name.
-            "source": entity.source,         // Serializes source.
-            "destination": entity.destination, // Serializes destination.
-            "time": entity.time,             // Serializes time as i64.
-            "seats": seats_json,             // Includes serialized seats.
-        });
-
-        // Appends the Vehicle JSON to the main array, matching C++'s j.push_back(entityJson).
-        if let Some(arr) = j.as_array_mut() {
-            arr.push(entity_json);
-        }
+    // Creates a FileIOImpl using the database path and lock timeout from a StorageConfig.
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self::with_path(config.database_path()).with_lock_timeout(config.lock_timeout)
+    }
 
-        // Opens the file for writing, creating it if it doesn't exist, and truncating existing content.
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.filename)?; // Propagates file open errors.
-        // Writes the JSON array to the file with pretty formatting (4-space indentation).
-        file.write_all(serde_json::to_string_pretty(&j)?.as_bytes())?;
-        Ok(()) // Returns success if writing completes.
-    }
-
-    // Reads Vehicles from db.json, equivalent to C++'s FileIO<Vehicle>::readFromFile.
-    fn read_from_file(&self) -> Result<Vec<Vehicle>, std::io::Error> {
-        let mut entities = Vec::new(); // Initializes an empty vector to store Vehicles.
-        // Attempts to open the file, returning an empty vector if it doesn't exist.
-        let mut file = match File::open(&self.filename) {
+    // Returns the path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Returns "<path><suffix>", used for the lock, temp and quarantine files kept next to the database.
+    fn sibling_path(&self, suffix: &str) -> PathBuf {
+        sibling_path(&self.path, suffix)
+    }
+
+    // Sets how long reads and writes wait for the database lock held by another process.
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    // Takes the advisory lock guarding the database: shared for reads, exclusive for read-modify-write
+    // cycles. It is released when the returned File is dropped.
+    fn lock(&self, exclusive: bool) -> std::io::Result<File> {
+        lock_database(&self.path, exclusive, self.lock_timeout)
+    }
+
+    // Reads the raw file contents, returning None if the file doesn't exist.
+    fn read_contents(&self) -> std::io::Result<Option<String>> {
+        let mut file = match File::open(&self.path) {
             Ok(file) => file,
-            Err(_) => return Ok(entities), // Matches C++'s behavior of returning empty vector.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
         };
-
         let mut contents = String::new(); // Buffer for file contents.
         file.read_to_string(&mut contents)?; // Reads file into string, propagates errors.
-        // Parses file contents as JSON, falling back to empty array if invalid.
-        let j: Value = serde_json::from_str(&contents).unwrap_or(json!([]));
-
-        // Iterates over JSON array elements, matching C++'s for (const auto& item : j).
-        if let Some(arr) = j.as_array() {
-            for item in arr {
-                // Creates a new Vehicle with default values, to be populated from JSON.
-                let mut vehicle = Vehicle {
-                    vehicle_id: item["vehicleId"].as_str().unwrap_or("").to_string(), // Deserializes vehicle_id, defaults to empty string.
-                    name: item["name"].as_str().unwrap_or("").to_string(),           // Deserializes name.
-                    source: item["source"].as_str().unwrap_or("").to_string(),       // Deserializes source.
-                    destination: item["destination"].as_str().unwrap_or("").to_string(), // Deserializes destination.
-                    time: item["time"].as_i64().unwrap_or(0),                        // Deserializes time, defaults to 0.
-                    seats: Vec::new(),                                              // Initializes empty seats vector.
-                };
-
-                // Deserializes seats from JSON, matching C++'s seats conversion.
-                if let Some(seats) = item["seats"].as_array() {
-                    for row_json in seats {
-                        let mut row = Vec::new(); // Initializes a row of Users.
-                        if let Some(users) = row_json.as_array() {
-                            for user_json in users {
-                                // Creates a User from JSON, matching C++'s user deserialization.
-                                let user = User {
-                                    user_id: user_json["userId"].as_str().unwrap_or("").to_string(),
-                                    name: user_json["name"].as_str().unwrap_or("").to_string(),
-                                    aadhar_card: user_json["aadharCard"].as_str().unwrap_or("").to_string(),
-                                };
-                                row.push(user); // Adds User to the row.
-                            }
-                        }
-                        vehicle.seats.push(row); // Adds row to seats.
-                    }
-                }
-                entities.push(vehicle); // Adds Vehicle to entities vector.
+        Ok(Some(contents))
+    }
+
+    // Reads the database from the file as an object of typed collections ({"trains": [...],
+    // "vehicles": [...]}), returning empty collections if the file doesn't exist or is empty.
+    fn read_json(&self) -> std::io::Result<Value> {
+        Ok(self.read_versioned()?.0)
+    }
+
+    // Reads the database and upgrades it to the current schema version in memory, reporting the
+    // migrations that were needed; the upgraded data is saved on the next write. A document that
+    // isn't a database at all is reported as a CorruptDatabase error.
+    fn read_versioned(&self) -> std::io::Result<(Value, MigrationReport)> {
+        let contents = match self.read_contents()? {
+            Some(contents) if !contents.trim().is_empty() => contents,
+            _ => return migrations::migrate(migrations::CURRENT_VERSION, empty_database()),
+        };
+        let corrupt = |line, column, message| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                CorruptDatabase { path: self.path.display().to_string(), line, column, message },
+            )
+        };
+        let doc = serde_json::from_str::<Value>(&contents).map_err(|e| {
+            // serde_json appends the location to its message; it is reported separately here.
+            let suffix = format!(" at line {} column {}", e.line(), e.column());
+            let message = e.to_string().trim_end_matches(&suffix).to_string();
+            corrupt(e.line(), e.column(), message)
+        })?;
+        let (version, data) = migrations::unwrap_envelope(doc).map_err(|e| corrupt(0, 0, e.to_string()))?;
+        migrations::migrate(version, data).map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidData => corrupt(0, 0, e.to_string()),
+            _ => e, // A newer schema version is not corruption; recover must not touch it.
+        })
+    }
+
+    // Upgrades the database file to the current schema version under the exclusive lock and returns
+    // what was done. With `dry_run` the file is left untouched and the report lists the migrations
+    // that would run.
+    pub fn migrate(&self, dry_run: bool) -> std::io::Result<MigrationReport> {
+        let _lock = self.lock(!dry_run)?;
+        let (data, report) = self.read_versioned()?;
+        if !dry_run && !report.applied.is_empty() {
+            self.write_json(&data)?;
+        }
+        Ok(report)
+    }
+
+    // Moves the database file aside to "<path>.corrupt-<unix time>" and returns the new path.
+    pub fn quarantine(&self) -> std::io::Result<String> {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let target = self.sibling_path(&format!(".corrupt-{}", secs));
+        fs::rename(&self.path, &target)?;
+        Ok(target.display().to_string())
+    }
+
+    // Recovery mode: if the file is corrupt, quarantines it and writes back every record that still
+    // parses. A healthy (or missing) file is left untouched.
+    pub fn recover(&self) -> std::io::Result<RecoveryReport> {
+        let _lock = self.lock(true)?;
+        let contents = match self.read_contents()? {
+            Some(contents) => contents,
+            None => return Ok(RecoveryReport { quarantined_to: None, salvaged: 0, discarded: 0 }),
+        };
+        match self.read_json() {
+            Ok(j) => {
+                let records = COLLECTIONS.iter().filter_map(|c| j[*c].as_array()).map(|arr| arr.len()).sum();
+                return Ok(RecoveryReport { quarantined_to: None, salvaged: records, discarded: 0 });
             }
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {}
+            Err(e) => return Err(e),
         }
-        Ok(entities) // Returns the vector of deserialized Vehicles.
-    }
-}
-// Implements FileIO trait for FileIOImpl<Train>, equivalent to C++'s FileIO<Train> specialization.
-impl FileIO for FileIOImpl<Train> {
-    type Item = Train;
-
-    // Saves a Train to db.json, equivalent to C++'s FileIO<Train>::saveToFile.
-    fn save_to_file(&self, entity: &Train) -> std::io::Result<()> {
-        // Initializes an empty JSON array to store data.
-        let mut j = json!([]);
-        // Attempts to read existing JSON data from the file.
-        if let Ok(mut file) = File::open(&self.filename) {
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)?;
-            j = serde_json::from_str(&contents).unwrap_or(json!([]));
+        let (records, discarded) = salvage_records(&contents);
+        let salvaged = records.len();
+        // Salvaged records are sorted into collections, then upgraded from the schema version the
+        // damaged file declared (collections of version 1 if it carried no envelope). From version 3
+        // on, the records with a userId are the registered users.
+        let version = salvage_version(&contents).max(1);
+        let mut data = migrations::split_collections(Value::Array(records))?;
+        if version >= 3 {
+            let (users, unclassified) = data[UNCLASSIFIED]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|record| record["userId"].is_string());
+            data[USERS] = Value::Array(users);
+            if unclassified.is_empty() {
+                if let Some(collections) = data.as_object_mut() {
+                    collections.remove(UNCLASSIFIED);
+                }
+            } else {
+                data[UNCLASSIFIED] = Value::Array(unclassified);
+            }
         }
+        let (data, _) = migrations::migrate(version, data)?;
+        let quarantined_to = self.quarantine()?;
+        self.write_json(&data)?;
+        Ok(RecoveryReport { quarantined_to: Some(quarantined_to), salvaged, discarded })
+    }
+
+    // Writes the database to the file with pretty formatting, wrapped in the envelope of the current
+    // schema version. The write is atomic: either the whole new database lands in the file or the
+    // previous contents remain intact.
+    fn write_json(&self, j: &Value) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(&migrations::wrap_envelope(j.clone()))?;
+        write_atomically(&self.path, |file| file.write_all(contents.as_bytes()))
+    }
+
+    // Reads the records of one collection (e.g. "vehicles") under a shared lock.
+    fn read_records(&self, collection: &str) -> std::io::Result<Vec<Value>> {
+        let _lock = self.lock(false)?;
+        let j = self.read_json()?;
+        Ok(j[collection].as_array().cloned().unwrap_or_default())
+    }
 
-        // Converts seats to JSON, identical to Vehicle's seats serialization.
-        let seats_json: Vec<Vec<serde_json::Value>> = entity.seats.iter().map(|row| {
-            row.iter().map(|user| {
-                json!({
-                    "userId": user.user_id,
-                    "name": user.name,
-                    "aadharCard": user.aadhar_card,
-                })
-            }).collect()
-        }).collect();
-
-        // Creates a JSON object for the Train, differing from Vehicle in the trainId key.
-        let entity_json = json!({
-            "trainId": entity.train_id,   // Uses train_id instead of vehicle_id.
-            "name": entity.name,
-            "source": entity.source,
-            "destination": entity.destination,
-            "time": entity.time,
-            "seats": seats_json,
-        });
-
-        // Appends the Train JSON to the main array.
-        if let Some(arr) = j.as_array_mut() {
-            arr.push(entity_json);
+    // Runs a read-modify-write cycle over one collection while holding the exclusive lock, so
+    // concurrent processes can't lose each other's updates. Other collections are written back as read.
+    fn update_records(&self, collection: &str, apply: &mut dyn FnMut(&mut Vec<Value>) -> std::io::Result<()>) -> std::io::Result<()> {
+        let _lock = self.lock(true)?;
+        let mut j = self.read_json()?;
+        let mut records = j[collection].as_array().cloned().unwrap_or_default();
+        apply(&mut records)?;
+        j[collection] = Value::Array(records);
+        self.write_json(&j)
+    }
+}
+
+// Implements Default for FileIOImpl, delegating to new().
+impl<T> Default for FileIOImpl<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Returns "<path><suffix>", e.g. "db.json.lock" for the lock file kept next to "db.json".
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Takes the advisory lock guarding the database at `path`, waiting up to `timeout` for other
+// processes: shared for reads, exclusive for writes. The lock lives on a sidecar "<path>.lock" file
+// because atomic writes replace the database file itself. It is released when the File is dropped.
+pub fn lock_database(path: &Path, exclusive: bool, timeout: Duration) -> std::io::Result<File> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sibling_path(path, ".lock"))?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let attempt = if exclusive { file.try_lock() } else { file.try_lock_shared() };
+        match attempt {
+            Ok(()) => return Ok(file),
+            Err(fs::TryLockError::WouldBlock) if Instant::now() < deadline => thread::sleep(LOCK_RETRY_INTERVAL),
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("could not lock {} within {:?}; another booking is in progress", path.display(), timeout),
+                ))
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(e),
         }
+    }
+}
 
-        // Writes the JSON array to the file with pretty formatting.
-        let mut file = OpenOptions::new()
+// Replaces `path` crash-safely: `write` fills a temporary file next to it, which is fsynced and then
+// renamed over `path`, and finally the directory entry is fsynced. If anything fails before the
+// rename, the temporary file is removed and `path` still holds its previous contents.
+pub fn write_atomically<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut File) -> std::io::Result<()>,
+{
+    let tmp_path = temp_path_for(path);
+    let result = (|| {
+        let mut tmp = OpenOptions::new()
             .write(true)
             .create(true)
-            .truncate(true)
-            .open(&self.filename)?;
-        file.write_all(serde_json::to_string_pretty(&j)?.as_bytes())?;
-        Ok(())
+            .truncate(true) // Overwrites a temp file left behind by an earlier interrupted write.
+            .open(&tmp_path)?;
+        write(&mut tmp)?;
+        tmp.sync_all()?; // Makes sure the data is on disk before it becomes visible under `path`.
+        fs::rename(&tmp_path, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path); // Best effort; the original file is untouched either way.
+        return Err(e);
     }
+    sync_parent_dir(path)
+}
 
-    // Reads Trains from db.json, equivalent to C++'s FileIO<Train>::readFromFile.
-    fn read_from_file(&self) -> Result<Vec<Train>, std::io::Error> {
-        let mut entities = Vec::new();
-        let mut file = match File::open(&self.filename) {
-            Ok(file) => file,
-            Err(_) => return Ok(entities), // Returns empty vector if file doesn't exist.
-        };
+// Returns the temporary file used by write_atomically for `path`: "<name>.tmp" in the same directory,
+// so the final rename never crosses filesystems.
+pub fn temp_path_for(path: &Path) -> std::path::PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
 
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let j: Value = serde_json::from_str(&contents).unwrap_or(json!([]));
-
-        if let Some(arr) = j.as_array() {
-            for item in arr {
-                let mut train = Train {
-                    train_id: item["trainId"].as_str().unwrap_or("").to_string(), // Uses trainId key.
-                    name: item["name"].as_str().unwrap_or("").to_string(),
-                    source: item["source"].as_str().unwrap_or("").to_string(),
-                    destination: item["destination"].as_str().unwrap_or("").to_string(),
-                    time: item["time"].as_i64().unwrap_or(0),
-                    seats: Vec::new(),
-                };
-
-                // Deserializes seats, identical to Vehicle's seats deserialization.
-                if let Some(seats) = item["seats"].as_array() {
-                    for row_json in seats {
-                        let mut row = Vec::new();
-                        if let Some(users) = row_json.as_array() {
-                            for user_json in users {
-                                let user = User {
-                                    user_id: user_json["userId"].as_str().unwrap_or("").to_string(),
-                                    name: user_json["name"].as_str().unwrap_or("").to_string(),
-                                    aadhar_card: user_json["aadharCard"].as_str().unwrap_or("").to_string(),
-                                };
-                                row.push(user);
-                            }
-                        }
-                        train.seats.push(row);
+// Flushes the directory entry of `path` so a completed rename survives a crash.
+#[cfg(unix)]
+pub(crate) fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+// Directories can't be opened for syncing on other platforms; the rename is already durable there.
+#[cfg(not(unix))]
+pub(crate) fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+// Returns a database with empty train, vehicle and user collections.
+pub(crate) fn empty_database() -> Value {
+    json!({ TRAINS: [], VEHICLES: [], USERS: [] })
+}
+
+// Finds the records in a (possibly damaged) database file and keeps the ones that still parse on
+// their own. Records are the objects directly inside an array: the top-level array of the oldest
+// layout, or a collection array of the newer ones. Returns the salvaged records and the number of
+// discarded ones.
+fn salvage_records(body: &str) -> (Vec<Value>, usize) {
+    let mut records = Vec::new();
+    let mut discarded = 0;
+    let mut stack: Vec<char> = Vec::new(); // Open containers, outermost first.
+    let mut in_string = false;     // Whether the scanner is inside a string literal.
+    let mut escaped = false;       // Whether the previous character was a backslash in a string.
+    let mut start = None;          // Byte offset and stack depth where the current record began.
+    for (i, c) in body.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                // A record is an object opened directly inside an array while no record is open.
+                if c == '{' && start.is_none() && stack.last() == Some(&'[') {
+                    start = Some((i, stack.len())); // Remembers where it began and the depth to return to.
+                }
+                stack.push(c);
+            }
+            '}' | ']' => {
+                stack.pop();
+                // The record ends when its own closing brace brings the stack back to where it began.
+                if let Some((begin, _)) = start.filter(|(_, depth)| *depth == stack.len()) {
+                    start = None;
+                    match serde_json::from_str::<Value>(&body[begin..=i]) {
+                        Ok(record) if record.is_object() => records.push(record),
+                        _ => discarded += 1,
                     }
                 }
-                entities.push(train);
             }
+            _ => {}
         }
-        Ok(entities)
     }
-}
\ No newline at end of file
+    // An object that was still open at the end of the file (e.g. a truncated write) is lost.
+    if start.is_some() {
+        discarded += 1;
+    }
+    (records, discarded)
+}
+
+// Finds the schema version declared by a (possibly damaged) database file, or 0 if it has none.
+fn salvage_version(body: &str) -> u32 {
+    let key = format!("\"{}\"", migrations::VERSION_KEY);
+    body.find(&key)
+        .map(|at| body[at + key.len()..].trim_start().trim_start_matches(':').trim_start())
+        .and_then(|rest| rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())].parse().ok())
+        .unwrap_or(0)
+}
+
+// Deserializes the records of a collection, reporting which record failed and why.
+pub(crate) fn records_to_entities<T: Record>(records: Vec<Value>) -> std::io::Result<Vec<T>> {
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| {
+            serde_json::from_value(record).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}[{}] is invalid: {}", T::COLLECTION, index, e),
+                )
+            })
+        })
+        .collect()
+}
+
+// Implements FileIO for every Record type: Serde does the mapping, so a new entity only needs a
+// Record impl, equivalent to C++'s FileIO<Vehicle>/FileIO<Train> specializations.
+impl<T: Record> FileIO for FileIOImpl<T> {
+    type Item = T;
+
+    // Saves an entity to its collection of db.json, replacing the stored record with the same id or
+    // appending it if this is the first save.
+    fn save_to_file(&self, entity: &T) -> std::io::Result<()> {
+        self.update_in_file(&mut |entities| {
+            match entities.iter_mut().find(|e| e.record_id() == entity.record_id()) {
+                Some(existing) => *existing = entity.clone(), // Overwrites the stored record in place.
+                None => entities.push(entity.clone()),        // First save for this id, so append it.
+            }
+            Ok(())
+        })
+    }
+
+    // Reads the entities of this type from db.json, empty if the file doesn't exist (matching C++).
+    fn read_from_file(&self) -> std::io::Result<Vec<T>> {
+        records_to_entities(self.read_records(T::COLLECTION)?)
+    }
+
+    // Deletes the entity with the given id from its collection.
+    fn delete_from_file(&self, id: &str) -> std::io::Result<bool> {
+        let mut removed = false;
+        self.update_in_file(&mut |entities| {
+            let before = entities.len();
+            entities.retain(|e| e.record_id() != id);
+            removed = entities.len() < before;
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
+    // Applies `apply` to the stored entities under the exclusive lock and writes them back.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<T>) -> std::io::Result<()>) -> std::io::Result<()> {
+        self.update_records(T::COLLECTION, &mut |records| {
+            let mut entities = records_to_entities::<T>(std::mem::take(records))?;
+            apply(&mut entities)?;
+            *records = entities.iter().map(serde_json::to_value).collect::<Result<_, _>>()?;
+            Ok(())
+        })
+    }
+
+    // Copies db.json under the shared lock, so no booking is half-written in the copy. A missing
+    // database is backed up as an empty one.
+    fn backup_to(&self, dir: &Path) -> std::io::Result<Vec<String>> {
+        let _lock = self.lock(false)?;
+        let contents = self.read_contents()?.unwrap_or_default();
+        write_atomically(&dir.join(BACKUP_FILE), |file| file.write_all(contents.as_bytes()))?;
+        Ok(vec![BACKUP_FILE.to_string()])
+    }
+
+    // Replaces db.json with the backed-up copy under the exclusive lock.
+    fn restore_from(&self, dir: &Path) -> std::io::Result<()> {
+        let contents = fs::read(dir.join(BACKUP_FILE))?;
+        let _lock = self.lock(true)?;
+        write_atomically(&self.path, |file| file.write_all(&contents))
+    }
+}
@@ -0,0 +1,352 @@
+// Imports HashSet to keep the PNRs given to migrated bookings unique.
+use std::collections::HashSet;
+// Imports io for reporting files that can't be migrated.
+use std::io;
+// Imports serde_json for working on the stored JSON directly; migrations never go through the
+// entity structs, so they keep working after those structs change.
+use serde_json::{json, Value};
+// Imports Pnr to give existing bookings their PNRs.
+use super::super::super::entities::pnr::Pnr;
+// Imports the prefix of encrypted Aadhar numbers, which migrations can't compare.
+use super::super::super::entities::aadhar::ENCRYPTED_PREFIX;
+
+// Schema version written by this program. Bump it together with a new entry in MIGRATIONS.
+pub const CURRENT_VERSION: u32 = 6;
+// Keys of the versioned envelope: {"schemaVersion": 6, "data": {"trains": [...], "vehicles": [...], "users": [...]}}.
+pub const VERSION_KEY: &str = "schemaVersion";
+pub const DATA_KEY: &str = "data";
+
+// One upgrade step, turning the data of schema version `from` into version `from + 1`.
+pub struct Migration {
+    pub from: u32,                               // Version the step upgrades from.
+    pub description: &'static str,              // Human-readable summary shown by `main migrate`.
+    pub apply: fn(Value, &mut Vec<RenamedUser>) -> io::Result<Value>, // Transforms the data (without the envelope), recording any passenger it renames.
+}
+
+// Every upgrade step, in order. Versions before 2 had no envelope:
+//   0: a flat array of train and vehicle records (the original db.json),
+//   1: {"trains": [...], "vehicles": [...]} collections, seats possibly still a grid of users,
+//   2: versioned envelope, seats always a coach layout holding the passenger's details,
+//   3: passengers kept once in a "users" registry, seats holding their userId,
+//   4: every booked seat holding the PNR of its booking,
+//   5: every seat holding its kind (window, aisle, lower berth, ...),
+//   6: every coach holding its class (SL, 3A, 2A, 1A, CC).
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "split the flat record array into trains/vehicles collections",
+        apply: |data, _| split_collections(data),
+    },
+    Migration {
+        from: 1,
+        description: "convert seat grids into coach/row/seat layouts",
+        apply: |data, _| seat_grid_to_layout(data),
+    },
+    Migration {
+        from: 2,
+        description: "move passengers into a users registry, seats referring to them by id",
+        apply: extract_users,
+    },
+    Migration {
+        from: 3,
+        description: "give every existing booking a PNR",
+        apply: |data, _| assign_pnrs(data),
+    },
+    Migration {
+        from: 4,
+        description: "mark every seat as a window, middle or aisle seat",
+        apply: |data, _| assign_seat_kinds(data),
+    },
+    Migration {
+        from: 5,
+        description: "class every existing coach as Chair Car",
+        apply: |data, _| assign_coach_classes(data),
+    },
+];
+
+// What a migration run did (or, in dry-run mode, would do).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,            // Schema version found in the file.
+    pub to_version: u32,              // Schema version after migrating.
+    pub applied: Vec<&'static str>,   // Descriptions of the steps run, in order.
+    pub renamed: Vec<RenamedUser>,    // Passengers registered under a new id by the steps.
+}
+
+// A passenger the users registry was given under a new id, because the userId on their seats was
+// already taken by a passenger with a different Aadhar number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamedUser {
+    pub user_id: String,        // The userId stored with their seats.
+    pub renamed_to: String,     // The id they are registered and seated under now, e.g. "u1~2".
+    pub name: String,           // Their name, to tell them apart from the passenger keeping the id.
+    pub seated_on: Vec<String>, //  The trains/vehicles they are seated on, as "trains/<id>" or "vehicles/<id>".
+}
+
+// Splits a stored document into its schema version and data, recognising the unversioned layouts.
+pub fn unwrap_envelope(doc: Value) -> io::Result<(u32, Value)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    match doc {
+        Value::Array(_) => Ok((0, doc)),
+        Value::Object(mut obj) if obj.contains_key(VERSION_KEY) => {
+            let version = obj[VERSION_KEY]
+                .as_u64()
+                .ok_or_else(|| invalid("schemaVersion must be a non-negative integer"))?;
+            let data = obj.remove(DATA_KEY).filter(Value::is_object).ok_or_else(|| invalid("missing \"data\" object"))?;
+            Ok((version as u32, data))
+        }
+        Value::Object(obj) if obj.values().all(Value::is_array) => Ok((1, Value::Object(obj))),
+        _ => Err(invalid("expected a versioned database object")),
+    }
+}
+
+// Wraps data of the current schema version in the envelope that is written to disk.
+pub fn wrap_envelope(data: Value) -> Value {
+    json!({ VERSION_KEY: CURRENT_VERSION, DATA_KEY: data })
+}
+
+// Upgrades `data` from `version` to CURRENT_VERSION one step at a time.
+pub fn migrate(version: u32, mut data: Value) -> io::Result<(Value, MigrationReport)> {
+    if version > CURRENT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "database schema version {} is newer than this program supports ({})",
+                version, CURRENT_VERSION
+            ),
+        ));
+    }
+    let mut applied = Vec::new();
+    let mut renamed = Vec::new();
+    for step in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        data = (step.apply)(data, &mut renamed)?;
+        applied.push(step.description);
+    }
+    Ok((data, MigrationReport { from_version: version, to_version: CURRENT_VERSION, applied, renamed }))
+}
+
+// Version 0 -> 1: sorts the flat record array into collections by id key. "trainId" records go to
+// trains, "vehicleId" records to vehicles and anything else to "unclassified", so nothing is lost.
+pub fn split_collections(data: Value) -> io::Result<Value> {
+    let records = match data {
+        Value::Array(records) => records,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a flat array of records")),
+    };
+    let mut collections = json!({ "trains": [], "vehicles": [] });
+    for record in records {
+        let collection = if record["trainId"].is_string() {
+            "trains"
+        } else if record["vehicleId"].is_string() {
+            "vehicles"
+        } else {
+            "unclassified"
+        };
+        match collections[collection].as_array_mut() {
+            Some(arr) => arr.push(record),
+            None => collections[collection] = json!([record]),
+        }
+    }
+    Ok(collections)
+}
+
+// Layout given to records migrated from a seat grid: coaches S1, S2, ... of 12 rows x 6 seats.
+const GRID_MIN_COACHES: usize = 3;
+const GRID_ROWS_PER_COACH: usize = 12;
+const GRID_SEATS_PER_ROW: usize = 6;
+
+// Version 1 -> 2: replaces seats stored as a grid of users with a coach layout, seating the users in
+// order from seat S1-1 and adding coaches if the default three are not enough.
+pub fn seat_grid_to_layout(mut data: Value) -> io::Result<Value> {
+    for collection in ["trains", "vehicles"] {
+        for record in data[collection].as_array_mut().into_iter().flatten() {
+            let Some(grid) = record["seats"].as_array() else { continue };
+            let users: Vec<Value> = grid.iter().filter_map(Value::as_array).flatten().cloned().collect();
+            let per_coach = GRID_ROWS_PER_COACH * GRID_SEATS_PER_ROW;
+            let coach_count = GRID_MIN_COACHES.max(users.len().div_ceil(per_coach));
+            let mut users = users.into_iter();
+            let coaches: Vec<Value> = (1..=coach_count)
+                .map(|c| {
+                    let rows: Vec<Vec<Value>> = (0..GRID_ROWS_PER_COACH)
+                        .map(|r| {
+                            (0..GRID_SEATS_PER_ROW)
+                                .map(|s| json!({
+                                    "number": r * GRID_SEATS_PER_ROW + s + 1,
+                                    "passenger": users.next(),
+                                }))
+                                .collect()
+                        })
+                        .collect();
+                    json!({ "code": format!("S{}", c), "rows": rows })
+                })
+                .collect();
+            record["seats"] = json!({ "coaches": coaches });
+        }
+    }
+    Ok(data)
+}
+
+// Version 2 -> 3: moves the passenger details out of every seat into the "users" collection and
+// stores the passenger's userId in the seat instead. Passengers registered under a new id (see
+// register_passenger) are added to `renamed`.
+pub fn extract_users(mut data: Value, renamed: &mut Vec<RenamedUser>) -> io::Result<Value> {
+    let mut users = Vec::new();
+    for (collection, id_key) in [("trains", "trainId"), ("vehicles", "vehicleId")] {
+        for record in data[collection].as_array_mut().into_iter().flatten() {
+            let entity = format!("{}/{}", collection, record[id_key].as_str().unwrap_or_default());
+            for coach in record["seats"]["coaches"].as_array_mut().into_iter().flatten() {
+                let seats = coach["rows"].as_array_mut().into_iter().flatten().filter_map(Value::as_array_mut).flatten();
+                for seat in seats {
+                    let passenger = seat["passenger"].take();
+                    if passenger.is_null() {
+                        continue;
+                    }
+                    let user_id = passenger["userId"].as_str().unwrap_or_default().to_string();
+                    let name = passenger["name"].as_str().unwrap_or_default().to_string();
+                    let registered_as = register_passenger(&mut users, passenger)?;
+                    if registered_as != user_id {
+                        note_rename(renamed, user_id, &registered_as, name, &entity);
+                    }
+                    seat["passenger"] = Value::String(registered_as);
+                }
+            }
+        }
+    }
+    data["users"] = Value::Array(users);
+    Ok(data)
+}
+
+// Adds `entity` to the rename of the passenger registered as `renamed_to`, recording the rename the
+// first time one of their seats is found.
+fn note_rename(renamed: &mut Vec<RenamedUser>, user_id: String, renamed_to: &str, name: String, entity: &str) {
+    let index = match renamed.iter().position(|r| r.renamed_to == renamed_to) {
+        Some(index) => index,
+        None => {
+            renamed.push(RenamedUser { user_id, renamed_to: renamed_to.to_string(), name, seated_on: Vec::new() });
+            renamed.len() - 1
+        }
+    };
+    if !renamed[index].seated_on.iter().any(|e| e == entity) {
+        renamed[index].seated_on.push(entity.to_string());
+    }
+}
+
+// Adds a seat's passenger to `users` and returns the id the seat should refer to. Passengers sharing
+// a userId are one user, registered with the details of the first seat found, unless their Aadhar
+// numbers differ: two people are never merged, so the later one is registered as "<userId>~2"
+// ("~3", ...). Encrypted numbers can't be compared without the key, so the file is refused when they
+// would need to be.
+fn register_passenger(users: &mut Vec<Value>, mut passenger: Value) -> io::Result<String> {
+    let user_id = passenger["userId"].as_str().unwrap_or_default().to_string();
+    let aadhar = passenger["aadharCard"].as_str().unwrap_or_default().to_string();
+    let mut candidate = user_id.clone();
+    for n in 2.. {
+        match users.iter().find(|u| u["userId"] == candidate.as_str()) {
+            None => break,
+            Some(user) if user["aadharCard"] == aadhar.as_str() => return Ok(candidate),
+            Some(user) if aadhar.starts_with(ENCRYPTED_PREFIX) || user["aadharCard"].as_str().is_some_and(|a| a.starts_with(ENCRYPTED_PREFIX)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("seats of user {} hold encrypted Aadhar numbers that can't be compared; decrypt them before upgrading", user_id),
+                ));
+            }
+            Some(_) => candidate = format!("{}~{}", user_id, n),
+        }
+    }
+    passenger["userId"] = Value::String(candidate.clone());
+    users.push(passenger);
+    Ok(candidate)
+}
+
+// Version 3 -> 4: gives every booked seat a PNR. A user's seats on one train/vehicle were a single
+// booking, so they share one PNR, derived from the booking so every run gives the same result.
+pub fn assign_pnrs(mut data: Value) -> io::Result<Value> {
+    for (collection, id_key) in [("trains", "trainId"), ("vehicles", "vehicleId")] {
+        let mut taken = HashSet::new();
+        for record in data[collection].as_array_mut().into_iter().flatten() {
+            let entity_id = record[id_key].as_str().unwrap_or_default().to_string();
+            let mut pnrs: Vec<(String, String)> = Vec::new();
+            for coach in record["seats"]["coaches"].as_array_mut().into_iter().flatten() {
+                let seats = coach["rows"].as_array_mut().into_iter().flatten().filter_map(Value::as_array_mut).flatten();
+                for seat in seats {
+                    let Some(user_id) = seat["passenger"].as_str().map(str::to_string) else { continue };
+                    let pnr = match pnrs.iter().find(|(u, _)| *u == user_id) {
+                        Some((_, pnr)) => pnr.clone(),
+                        None => {
+                            let pnr = legacy_pnr(collection, &entity_id, &user_id, &mut taken);
+                            pnrs.push((user_id, pnr.clone()));
+                            pnr
+                        }
+                    };
+                    seat["pnr"] = Value::String(pnr);
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+// Returns the PNR of a booking made before PNRs existed: derived from the collection, train/vehicle
+// and user, and unique among `taken`, which it is added to. Also used by the SQLite backend's upgrade.
+pub fn legacy_pnr(collection: &str, entity_id: &str, user_id: &str, taken: &mut HashSet<String>) -> String {
+    for attempt in 0.. {
+        let pnr = Pnr::from_seed(&format!("{}/{}/{}/{}", collection, entity_id, user_id, attempt)).as_str().to_string();
+        if taken.insert(pnr.clone()) {
+            return pnr;
+        }
+    }
+    unreachable!("some attempt yields an unused PNR")
+}
+
+// Version 4 -> 5: gives every seat its kind. Every layout before version 5 was rows of seats, so a
+// seat's kind follows from its place in the row; seats that already have one keep it.
+pub fn assign_seat_kinds(mut data: Value) -> io::Result<Value> {
+    for collection in ["trains", "vehicles"] {
+        for record in data[collection].as_array_mut().into_iter().flatten() {
+            for coach in record["seats"]["coaches"].as_array_mut().into_iter().flatten() {
+                for row in coach["rows"].as_array_mut().into_iter().flatten().filter_map(Value::as_array_mut) {
+                    let width = row.len();
+                    for (column, seat) in row.iter_mut().enumerate() {
+                        if seat.is_object() && seat["kind"].is_null() {
+                            seat["kind"] = Value::from(row_seat_kind(column, width));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+// Returns the stored kind of the seat at `column` of a row `width` seats wide: window seats at the
+// ends, aisle seats either side of an aisle after the larger half of the row, middle seats between.
+// Also used by the SQLite backend's upgrade.
+pub fn row_seat_kind(column: usize, width: usize) -> &'static str {
+    let aisle = width.div_ceil(2);
+    if column == 0 || column + 1 == width {
+        "window"
+    } else if column + 1 == aisle || column == aisle {
+        "aisle"
+    } else {
+        "middle"
+    }
+}
+
+// Class given to coaches stored before coaches had one. They were rows of window, middle and aisle
+// seats (see assign_seat_kinds), not berths, so they are sold as Chair Car, keeping their S1, S2, ...
+// codes. Also used by the SQLite backend's upgrade.
+pub const LEGACY_COACH_CLASS: &str = "CC";
+
+// Version 5 -> 6: gives every coach a class. Coaches keep their layout and bookings; coaches that
+// already have a class keep it.
+pub fn assign_coach_classes(mut data: Value) -> io::Result<Value> {
+    for collection in ["trains", "vehicles"] {
+        for record in data[collection].as_array_mut().into_iter().flatten() {
+            for coach in record["seats"]["coaches"].as_array_mut().into_iter().flatten() {
+                if coach.is_object() && coach["class"].is_null() {
+                    coach["class"] = Value::from(LEGACY_COACH_CLASS);
+                }
+            }
+        }
+    }
+    Ok(data)
+}
@@ -0,0 +1,646 @@
+// Imports fs and OpenOptions to read the journal and append to it.
+use std::fs::{self, File, OpenOptions};
+// Imports io for the FileIO error type, and Seek and Write to append entries.
+use std::io::{self, Seek, SeekFrom, Write};
+// Imports Path and PathBuf for the journal location and its sidecar files.
+use std::path::{Path, PathBuf};
+// Imports time types to timestamp entries and bound lock waits.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+// Imports Serde traits for the journal lines.
+use serde::{Deserialize, Serialize};
+// Imports serde_json; events carry entities and passengers as JSON values, so the journal can be
+// replayed and migrated at the JSON level like db.json.
+use serde_json::Value;
+// Imports the seat number and PNR events refer to.
+use super::super::entities::pnr::Pnr;
+use super::super::entities::seat_layout::SeatNumber;
+// Imports the storage trait, the shared locking and atomic-write helpers, and the schema migrations.
+use super::file_io::migrations::{self, CURRENT_VERSION};
+use super::file_io::{
+    empty_database, lock_database, records_to_entities, sibling_path, sync_parent_dir, write_atomically, FileIO,
+    Record, DEFAULT_LOCK_TIMEOUT,
+};
+// Imports the storage configuration used by from_config.
+use super::config::StorageConfig;
+
+// Journal path used by the journal backend when nothing else is configured.
+pub const DEFAULT_JOURNAL_PATH: &str = "db.journal";
+// Version of the journal line format, stored in the header line.
+pub const JOURNAL_FORMAT: u32 = 1;
+// Names of the journal and snapshot inside a backup directory.
+const JOURNAL_BACKUP: &str = "journal.jsonl";
+const SNAPSHOT_BACKUP: &str = "snapshot.json";
+// Number of entries appended between two automatic snapshots.
+pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 100;
+
+// One change recorded in the journal. Entities and passengers are stored as JSON values in the
+// schema version named by the journal header.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all_fields = "camelCase")]
+pub enum JournalEvent {
+    // A train/vehicle was stored for the first time; its seats are empty, bookings follow as events.
+    EntityCreated { collection: String, id: String, entity: Value },
+    // A train/vehicle changed in more than its bookings; `entity` is its complete new state.
+    EntityUpdated { collection: String, id: String, entity: Value },
+    // A train/vehicle was removed.
+    EntityDeleted { collection: String, id: String },
+    // A passenger was seated; `passenger` is their userId (their full details before schema version 3)
+    // and `pnr` the booking's PNR (absent before schema version 4).
+    BookingCreated {
+        collection: String,
+        entity_id: String,
+        seat: SeatNumber,
+        passenger: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pnr: Option<Pnr>,
+    },
+    // A seat was freed.
+    BookingCancelled { collection: String, entity_id: String, seat: SeatNumber, user_id: String },
+    // The details stored for a seated passenger changed, e.g. an Aadhar number re-encrypted under a new
+    // key. Only written before schema version 3; since then passenger details change in "users".
+    PassengerUpdated { collection: String, entity_id: String, seat: SeatNumber, passenger: Value },
+}
+
+// One line of the journal: the events of a single update, which are replayed all or nothing.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub seq: u64,                  // Sequence number, increasing by one per entry.
+    pub at: i64,                   // Unix timestamp of the update.
+    pub events: Vec<JournalEvent>, // What changed.
+}
+
+// First line of the journal.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JournalHeader {
+    journal: u32,        // Line format version (JOURNAL_FORMAT).
+    schema_version: u32, // Schema version of the entities and passengers in the events.
+    base_seq: u64,       // Sequence number of the last entry before this journal, i.e. its snapshot.
+}
+
+// Summarises a compaction run.
+#[derive(Debug)]
+pub struct CompactionReport {
+    pub archived_to: Option<String>, // Where the old journal was moved, None if it held no entries.
+    pub entries: usize,             // Entries in the archived journal.
+    pub seq: u64,                   // Sequence number the new snapshot is taken at.
+}
+
+// The database state rebuilt from the snapshot and the journal.
+struct JournalState {
+    data: Value,          // Collections at the current schema version.
+    journal_version: u32, // Schema version of the journal's events (CURRENT_VERSION if there is no journal).
+    journal_exists: bool, // Whether the journal file exists.
+    entries: usize,       // Entries in the journal file.
+    since_snapshot: usize, // Entries replayed on top of the snapshot.
+    last_seq: u64,        // Sequence number of the last entry (or of the snapshot).
+    valid_len: u64,       // Length of the journal up to its last complete line.
+}
+
+// Stores entities as an append-only journal of events next to a periodic snapshot of the full state:
+//   <path>            the journal: a header line, then one JSON line per update,
+//   <path>.snapshot   the state at some sequence number, in the versioned db.json layout,
+//   <path>.archive-N  journals moved aside by `compact`, ending at sequence number N,
+//   <path>.replaced-T the files a restore at unix time T replaced.
+// The current state is the snapshot with the newer journal entries replayed on top. Writes only
+// append a line, and the journal plus its archives form the complete history of every booking.
+pub struct JournalIOImpl<T> {
+    path: PathBuf,                         // Path of the journal file.
+    lock_timeout: Duration,                // How long to wait for another process's lock.
+    snapshot_interval: usize,              // Entries between automatic snapshots.
+    _phantom: std::marker::PhantomData<T>, // PhantomData ensures type safety for the generic T.
+}
+
+impl<T> JournalIOImpl<T> {
+    // Creates a JournalIOImpl using "db.journal" in the current directory.
+    pub fn new() -> Self {
+        Self::with_path(DEFAULT_JOURNAL_PATH)
+    }
+
+    // Creates a JournalIOImpl storing its journal at `path`.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        JournalIOImpl {
+            path: path.into(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    // Creates a JournalIOImpl using the database path and lock timeout from a StorageConfig.
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self::with_path(config.database_path()).with_lock_timeout(config.lock_timeout)
+    }
+
+    // Sets how long operations wait for the lock held by another process.
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    // Sets how many entries are appended between automatic snapshots.
+    pub fn with_snapshot_interval(mut self, entries: usize) -> Self {
+        self.snapshot_interval = entries.max(1);
+        self
+    }
+
+    // Returns the path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Returns the path of the snapshot file.
+    pub fn snapshot_path(&self) -> PathBuf {
+        sibling_path(&self.path, ".snapshot")
+    }
+
+    // Lists the existing files of the store with their names inside a backup.
+    fn backup_files(&self) -> io::Result<Vec<(PathBuf, String)>> {
+        let mut files = Vec::new();
+        for (source, name) in [(self.path.clone(), JOURNAL_BACKUP), (self.snapshot_path(), SNAPSHOT_BACKUP)] {
+            if source.is_file() {
+                files.push((source, name.to_string()));
+            }
+        }
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = format!("{}.archive-", self.path.file_name().unwrap_or_default().to_string_lossy());
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(seq) = name.strip_prefix(&prefix) {
+                files.push((entry.path(), format!("archive-{}.jsonl", seq)));
+            }
+        }
+        Ok(files)
+    }
+
+    // Copies the journal, snapshot and archives of the backup in `dir` into the empty directory
+    // `staging`, synced to disk. Returns each copy's name with the path it is restored to.
+    fn stage_restore(&self, dir: &Path, staging: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+        // A staging directory left by an interrupted restore holds nothing that is in use.
+        match fs::remove_dir_all(staging) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        fs::create_dir(staging)?;
+        let mut staged = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            let target = match name.as_str() {
+                JOURNAL_BACKUP => self.path.clone(),
+                SNAPSHOT_BACKUP => self.snapshot_path(),
+                _ => match name.strip_prefix("archive-").and_then(|n| n.strip_suffix(".jsonl")) {
+                    Some(seq) => sibling_path(&self.path, &format!(".archive-{}", seq)),
+                    None => continue,
+                },
+            };
+            fs::copy(dir.join(&name), staging.join(&name))?;
+            File::open(staging.join(&name))?.sync_all()?;
+            staged.push((name, target));
+        }
+        Ok(staged)
+    }
+
+    // Creates the directory a restore moves the current files into: "<path>.replaced-<unix time>",
+    // with "-2", "-3", ... appended if an earlier restore in the same second took that name.
+    fn replaced_dir(&self) -> io::Result<PathBuf> {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut attempt = 1;
+        loop {
+            let suffix = match attempt {
+                1 => format!(".replaced-{}", secs),
+                n => format!(".replaced-{}-{}", secs, n),
+            };
+            let dir = sibling_path(&self.path, &suffix);
+            match fs::create_dir(&dir) {
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                result => return result.map(|_| dir),
+            }
+        }
+    }
+
+    // Reads the snapshot, returning its schema version, data and sequence number, or None if there
+    // is no snapshot yet.
+    fn read_snapshot(&self) -> io::Result<Option<(u32, Value, u64)>> {
+        let contents = match fs::read_to_string(self.snapshot_path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let doc: Value = serde_json::from_str(&contents).map_err(|e| corrupt(&self.snapshot_path(), e))?;
+        let seq = doc["seq"].as_u64().unwrap_or(0);
+        let (version, data) = migrations::unwrap_envelope(doc).map_err(|e| corrupt(&self.snapshot_path(), e))?;
+        Ok(Some((version, data, seq)))
+    }
+
+    // Rebuilds the current state: the snapshot plus every newer journal entry. A last line without
+    // its newline is an append that was cut short by a crash; it is ignored and overwritten later.
+    fn load(&self) -> io::Result<JournalState> {
+        let snapshot = self.read_snapshot()?;
+        let has_snapshot = snapshot.is_some();
+        let (snapshot_version, mut data, snapshot_seq) = snapshot.unwrap_or((CURRENT_VERSION, empty_database(), 0));
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let mut state = JournalState {
+            data: Value::Null,
+            journal_version: CURRENT_VERSION,
+            journal_exists: contents.is_some(),
+            entries: 0,
+            since_snapshot: 0,
+            last_seq: snapshot_seq,
+            valid_len: 0,
+        };
+        let mut version = snapshot_version;
+        let contents = contents.unwrap_or_default();
+        let mut offset = 0;
+        for (index, line) in contents.split_inclusive('\n').enumerate() {
+            if !line.ends_with('\n') {
+                break; // A torn final append.
+            }
+            offset += line.len();
+            if index == 0 {
+                let header: JournalHeader = serde_json::from_str(line).map_err(|e| corrupt(&self.path, e))?;
+                if header.journal > JOURNAL_FORMAT {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!("{} uses journal format {}, newer than this program supports", self.path.display(), header.journal),
+                    ));
+                }
+                state.journal_version = header.schema_version;
+                if !has_snapshot {
+                    // Without a snapshot the journal starts from an empty database, which is valid
+                    // in the journal's own schema version.
+                    version = header.schema_version;
+                }
+                state.valid_len = offset as u64;
+            } else {
+                let entry: JournalEntry = serde_json::from_str(line).map_err(|e| corrupt(&self.path, e))?;
+                state.entries += 1;
+                // Complete lines are kept by the next append, including those the snapshot covers.
+                state.valid_len = offset as u64;
+                if entry.seq <= snapshot_seq {
+                    continue; // Already contained in the snapshot.
+                }
+                if version != state.journal_version {
+                    // Events apply to data of the journal's schema version. A snapshot can only be
+                    // older than the journal after an interrupted compaction, which starts the new
+                    // journal at the current version.
+                    if version > state.journal_version || state.journal_version != CURRENT_VERSION {
+                        return Err(corrupt(&self.path, "snapshot and journal schema versions don't match"));
+                    }
+                    data = migrations::migrate(version, data)?.0;
+                    version = CURRENT_VERSION;
+                }
+                for event in &entry.events {
+                    apply_event(&mut data, event).map_err(|e| corrupt(&self.path, format!("entry {}: {}", entry.seq, e)))?;
+                }
+                state.since_snapshot += 1;
+                state.last_seq = entry.seq;
+            }
+        }
+        state.data = migrations::migrate(version, data)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::InvalidData => corrupt(&self.path, e),
+                _ => e,
+            })?
+            .0;
+        Ok(state)
+    }
+
+    // Writes the state as the new snapshot, taken at sequence number `seq`.
+    fn write_snapshot(&self, data: &Value, seq: u64) -> io::Result<()> {
+        let mut doc = migrations::wrap_envelope(data.clone());
+        doc["seq"] = Value::from(seq);
+        let contents = serde_json::to_string_pretty(&doc)?;
+        write_atomically(&self.snapshot_path(), |file| file.write_all(contents.as_bytes()))
+    }
+
+    // Starts an empty journal at the current schema version, continuing after `base_seq`.
+    fn start_journal(&self, base_seq: u64) -> io::Result<()> {
+        let header = JournalHeader { journal: JOURNAL_FORMAT, schema_version: CURRENT_VERSION, base_seq };
+        let line = format!("{}\n", serde_json::to_string(&header)?);
+        write_atomically(&self.path, |file| file.write_all(line.as_bytes()))
+    }
+
+    // Snapshots the state and moves the journal aside to "<path>.archive-<last seq>", then starts
+    // a new journal. Must be called with the exclusive lock held.
+    fn compact_locked(&self, state: &JournalState) -> io::Result<CompactionReport> {
+        self.write_snapshot(&state.data, state.last_seq)?;
+        let mut archived_to = None;
+        if state.journal_exists && state.entries > 0 {
+            let target = sibling_path(&self.path, &format!(".archive-{}", state.last_seq));
+            fs::rename(&self.path, &target)?;
+            archived_to = Some(target.display().to_string());
+        }
+        self.start_journal(state.last_seq)?;
+        Ok(CompactionReport { archived_to, entries: state.entries, seq: state.last_seq })
+    }
+
+    // Compaction: writes a snapshot of the current state, archives the journal it covers and starts
+    // a new, empty journal. Loading then only has to read the snapshot.
+    pub fn compact(&self) -> io::Result<CompactionReport> {
+        let _lock = lock_database(&self.path, true, self.lock_timeout)?;
+        let state = self.load()?;
+        self.compact_locked(&state)
+    }
+
+    // Appends one entry holding `events` and updates `state`, snapshotting when the interval is reached.
+    fn append(&self, state: &mut JournalState, events: Vec<JournalEvent>) -> io::Result<()> {
+        if !state.journal_exists || state.journal_version != CURRENT_VERSION {
+            // New events are written in the current schema version, so a missing journal is started
+            // and one written by an older version is compacted first.
+            if state.journal_exists {
+                self.compact_locked(state)?;
+            } else {
+                self.start_journal(state.last_seq)?;
+            }
+            state.journal_exists = true;
+            state.journal_version = CURRENT_VERSION;
+            state.entries = 0;
+            state.since_snapshot = 0;
+            state.valid_len = fs::metadata(&self.path)?.len();
+        }
+        let entry = JournalEntry {
+            seq: state.last_seq + 1,
+            at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64,
+            events,
+        };
+        let line = format!("{}\n", serde_json::to_string(&entry)?);
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+        file.set_len(state.valid_len)?; // Drops a torn line left by an earlier crash.
+        file.seek(SeekFrom::End(0))?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?; // The entry is durable before the update is reported as done.
+        for event in &entry.events {
+            apply_event(&mut state.data, event)?;
+        }
+        state.last_seq = entry.seq;
+        state.entries += 1;
+        state.since_snapshot += 1;
+        state.valid_len += line.len() as u64;
+        if state.since_snapshot >= self.snapshot_interval {
+            self.write_snapshot(&state.data, state.last_seq)?;
+            state.since_snapshot = 0;
+        }
+        Ok(())
+    }
+}
+
+// Implements Default for JournalIOImpl, delegating to new().
+impl<T> Default for JournalIOImpl<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Reports an unreadable journal or snapshot as InvalidData, i.e. a corrupt database.
+fn corrupt(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{} is corrupt: {}", path.display(), e))
+}
+
+// Name of the id field of the records in a collection, as stored by the journal.
+fn id_key(collection: &str) -> &'static str {
+    match collection {
+        "trains" => "trainId",
+        "vehicles" => "vehicleId",
+        "users" => "userId",
+        _ => "id",
+    }
+}
+
+// Returns the records of a collection, creating it if needed.
+fn collection_mut<'a>(data: &'a mut Value, collection: &str) -> &'a mut Vec<Value> {
+    if !data[collection].is_array() {
+        data[collection] = Value::Array(Vec::new());
+    }
+    data[collection].as_array_mut().expect("collection was just made an array")
+}
+
+// Returns a seat, or an error if the journal refers to an unknown seat.
+fn seat_mut<'a>(data: &'a mut Value, collection: &str, entity_id: &str, seat: &SeatNumber) -> io::Result<&'a mut Value> {
+    let key = id_key(collection);
+    collection_mut(data, collection)
+        .iter_mut()
+        .find(|r| r[key] == entity_id)
+        .and_then(|record| record["seats"]["coaches"].as_array_mut())
+        .and_then(|coaches| coaches.iter_mut().find(|c| c["code"] == seat.coach.as_str()))
+        .and_then(|coach| coach["rows"].as_array_mut())
+        .and_then(|rows| {
+            rows.iter_mut()
+                .filter_map(Value::as_array_mut)
+                .flatten()
+                .find(|s| s["number"] == seat.seat)
+        })
+        .ok_or_else(|| io::Error::other(format!("unknown seat {} of {}/{}", seat, collection, entity_id)))
+}
+
+// Applies one event to the collections.
+fn apply_event(data: &mut Value, event: &JournalEvent) -> io::Result<()> {
+    match event {
+        JournalEvent::EntityCreated { collection, id, entity } | JournalEvent::EntityUpdated { collection, id, entity } => {
+            let key = id_key(collection);
+            let records = collection_mut(data, collection);
+            match records.iter_mut().find(|r| r[key] == id.as_str()) {
+                Some(record) => *record = entity.clone(),
+                None => records.push(entity.clone()),
+            }
+        }
+        JournalEvent::EntityDeleted { collection, id } => {
+            let key = id_key(collection);
+            collection_mut(data, collection).retain(|r| r[key] != id.as_str());
+        }
+        JournalEvent::BookingCreated { collection, entity_id, seat, passenger, pnr } => {
+            let seat = seat_mut(data, collection, entity_id, seat)?;
+            seat["passenger"] = passenger.clone();
+            if let Some(pnr) = pnr {
+                seat["pnr"] = Value::from(pnr.as_str());
+            }
+        }
+        JournalEvent::PassengerUpdated { collection, entity_id, seat, passenger } => {
+            seat_mut(data, collection, entity_id, seat)?["passenger"] = passenger.clone();
+        }
+        JournalEvent::BookingCancelled { collection, entity_id, seat, .. } => {
+            let seat = seat_mut(data, collection, entity_id, seat)?;
+            seat["passenger"] = Value::Null;
+            if let Some(seat) = seat.as_object_mut() {
+                seat.remove("pnr");
+            }
+        }
+    }
+    Ok(())
+}
+
+// Describes the change from `before` to `after` as events: bookings and cancellations seat by seat,
+// and whole entities when anything besides the bookings changed. Records without seats (users) are
+// created, updated and deleted as a whole.
+fn diff<T: Record>(before: &[T], after: &[T]) -> io::Result<Vec<JournalEvent>> {
+    let collection = T::COLLECTION.to_string();
+    let without_passengers = |entity: &T| {
+        let mut entity = entity.clone();
+        if let Some(seats) = entity.seats_mut() {
+            seats.clear_passengers();
+        }
+        serde_json::to_value(entity)
+    };
+    let occupied = |entity: &T| -> Vec<(SeatNumber, Value, Option<Pnr>)> {
+        let seats = entity.seats().map(|s| s.occupied()).unwrap_or_default();
+        seats.into_iter().map(|(number, seat)| (number, Value::from(seat.passenger.clone()), seat.pnr.clone())).collect()
+    };
+    let mut events = Vec::new();
+    for entity in after {
+        let id = entity.record_id().to_string();
+        let old = before.iter().find(|b| b.record_id() == id);
+        let old_seats = match old {
+            None => {
+                let created = without_passengers(entity)?;
+                events.push(JournalEvent::EntityCreated { collection: collection.clone(), id: id.clone(), entity: created });
+                Vec::new()
+            }
+            Some(old) if without_passengers(old)? != without_passengers(entity)? => {
+                let updated = serde_json::to_value(entity)?;
+                events.push(JournalEvent::EntityUpdated { collection: collection.clone(), id, entity: updated });
+                continue;
+            }
+            Some(old) => occupied(old),
+        };
+        let new_seats = occupied(entity);
+        for booking @ (seat, passenger, _) in &old_seats {
+            if !new_seats.contains(booking) {
+                let user_id = passenger.as_str().unwrap_or_default().to_string();
+                events.push(JournalEvent::BookingCancelled {
+                    collection: collection.clone(),
+                    entity_id: id.clone(),
+                    seat: seat.clone(),
+                    user_id,
+                });
+            }
+        }
+        for booking in new_seats {
+            if !old_seats.contains(&booking) {
+                let (seat, passenger, pnr) = booking;
+                events.push(JournalEvent::BookingCreated { collection: collection.clone(), entity_id: id.clone(), seat, passenger, pnr });
+            }
+        }
+    }
+    for old in before.iter().filter(|b| !after.iter().any(|a| a.record_id() == b.record_id())) {
+        events.push(JournalEvent::EntityDeleted { collection: collection.clone(), id: old.record_id().to_string() });
+    }
+    Ok(events)
+}
+
+// Implements FileIO over the journal. Every change is appended as one entry.
+impl<T: Record> FileIO for JournalIOImpl<T> {
+    type Item = T;
+
+    // Saves an entity, replacing the stored one with the same id.
+    fn save_to_file(&self, entity: &T) -> io::Result<()> {
+        self.update_in_file(&mut |entities| {
+            match entities.iter_mut().find(|e| e.record_id() == entity.record_id()) {
+                Some(existing) => *existing = entity.clone(),
+                None => entities.push(entity.clone()),
+            }
+            Ok(())
+        })
+    }
+
+    // Rebuilds the entities of this type from the snapshot and journal.
+    fn read_from_file(&self) -> io::Result<Vec<T>> {
+        let _lock = lock_database(&self.path, false, self.lock_timeout)?;
+        let state = self.load()?;
+        records_to_entities(state.data[T::COLLECTION].as_array().cloned().unwrap_or_default())
+    }
+
+    // Deletes the entity with the given id.
+    fn delete_from_file(&self, id: &str) -> io::Result<bool> {
+        let mut removed = false;
+        self.update_in_file(&mut |entities| {
+            let before = entities.len();
+            entities.retain(|e| e.record_id() != id);
+            removed = entities.len() < before;
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
+    // Applies `apply` under the exclusive lock and appends the resulting events; nothing is written
+    // if `apply` fails or changes nothing.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<T>) -> io::Result<()>) -> io::Result<()> {
+        let _lock = lock_database(&self.path, true, self.lock_timeout)?;
+        let mut state = self.load()?;
+        let before: Vec<T> = records_to_entities(state.data[T::COLLECTION].as_array().cloned().unwrap_or_default())?;
+        let mut after = before.clone();
+        apply(&mut after)?;
+        let events = diff(&before, &after)?;
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.append(&mut state, events)
+    }
+
+    // Copies the journal, its snapshot and its archives under the shared lock, as "journal.jsonl",
+    // "snapshot.json" and "archive-<seq>.jsonl".
+    fn backup_to(&self, dir: &Path) -> io::Result<Vec<String>> {
+        let _lock = lock_database(&self.path, false, self.lock_timeout)?;
+        let mut written = Vec::new();
+        for (source, name) in self.backup_files()? {
+            fs::copy(&source, dir.join(&name))?;
+            written.push(name);
+        }
+        Ok(written)
+    }
+
+    // Replaces the journal, snapshot and archives with the backed-up ones as one set, under the
+    // exclusive lock. The backup is first copied into "<path>.restoring", so a failed copy changes
+    // nothing. Every current file, archives the backup doesn't have included, is then moved aside to
+    // "<path>.replaced-<unix time>" and the copies are renamed into place; if a rename fails, the
+    // files moved so far are put back.
+    fn restore_from(&self, dir: &Path) -> io::Result<()> {
+        let _lock = lock_database(&self.path, true, self.lock_timeout)?;
+        let staging = sibling_path(&self.path, ".restoring");
+        let staged = match self.stage_restore(dir, &staging) {
+            Ok(staged) => staged,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+        let replaced = self.replaced_dir()?;
+        let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new(); // (original path, where it was moved to)
+        let mut placed: Vec<PathBuf> = Vec::new();
+        let result = (|| {
+            for (current, name) in self.backup_files()? {
+                let aside = replaced.join(name);
+                fs::rename(&current, &aside)?;
+                moved.push((current, aside));
+            }
+            for (name, target) in &staged {
+                fs::rename(staging.join(name), target)?;
+                placed.push(target.clone());
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            // Best effort: the files moved aside are still complete, so the earlier state can be put back.
+            for target in &placed {
+                let _ = fs::remove_file(target);
+            }
+            for (current, aside) in moved.iter().rev() {
+                let _ = fs::rename(aside, current);
+            }
+            let _ = fs::remove_dir_all(&staging);
+            let _ = fs::remove_dir(&replaced);
+            return Err(e);
+        }
+        fs::remove_dir(&staging)?;
+        if moved.is_empty() {
+            fs::remove_dir(&replaced)?;
+        }
+        sync_parent_dir(&self.path)
+    }
+}
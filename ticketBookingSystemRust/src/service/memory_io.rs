@@ -0,0 +1,105 @@
+// Imports io for the FileIO error type.
+use std::io;
+// Imports Path for the backup directory of the FileIO signatures.
+use std::path::Path;
+// Imports Arc and Mutex so clones of a MemoryIOImpl share one store, like processes sharing db.json.
+use std::sync::{Arc, Mutex, MutexGuard};
+// Imports the storage trait implemented here.
+use super::file_io::{FileIO, Record};
+
+// Keeps entities in memory instead of a file, for tests and simulations without filesystem side
+// effects. Clones share the same entities, so a test can hand one clone to a BookingServiceImpl and
+// inspect the stored state through another.
+pub struct MemoryIOImpl<T> {
+    entities: Arc<Mutex<Vec<T>>>, // The stored entities, in insertion order.
+}
+
+impl<T> MemoryIOImpl<T> {
+    // Creates an empty in-memory store.
+    pub fn new() -> Self {
+        Self::with_entities(Vec::new())
+    }
+
+    // Creates an in-memory store that already holds `entities`.
+    pub fn with_entities(entities: Vec<T>) -> Self {
+        MemoryIOImpl { entities: Arc::new(Mutex::new(entities)) }
+    }
+
+    // Locks the store; a panic during an earlier update is reported as an error instead of propagating.
+    fn lock(&self) -> io::Result<MutexGuard<'_, Vec<T>>> {
+        self.entities
+            .lock()
+            .map_err(|_| io::Error::other("in-memory store is poisoned by a panic during an update"))
+    }
+}
+
+// Implements Clone by sharing the store rather than copying it.
+impl<T> Clone for MemoryIOImpl<T> {
+    fn clone(&self) -> Self {
+        MemoryIOImpl { entities: Arc::clone(&self.entities) }
+    }
+}
+
+// Implements Default for MemoryIOImpl, delegating to new().
+impl<T> Default for MemoryIOImpl<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implements FileIO with the same semantics as the file-backed stores.
+impl<T: Record> FileIO for MemoryIOImpl<T> {
+    type Item = T;
+
+    // Saves an entity, replacing the stored one with the same id or appending it.
+    fn save_to_file(&self, entity: &T) -> io::Result<()> {
+        let mut entities = self.lock()?;
+        match entities.iter_mut().find(|e| e.record_id() == entity.record_id()) {
+            Some(existing) => *existing = entity.clone(),
+            None => entities.push(entity.clone()),
+        }
+        Ok(())
+    }
+
+    // Returns a copy of every stored entity.
+    fn read_from_file(&self) -> io::Result<Vec<T>> {
+        Ok(self.lock()?.clone())
+    }
+
+    // Deletes the entity with the given id.
+    fn delete_from_file(&self, id: &str) -> io::Result<bool> {
+        let mut entities = self.lock()?;
+        let before = entities.len();
+        entities.retain(|e| e.record_id() != id);
+        Ok(entities.len() < before)
+    }
+
+    // Applies `apply` to a copy of the entities while holding the lock, keeping the result only if
+    // `apply` succeeds, so a failed update leaves the store unchanged as it does for db.json.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<T>) -> io::Result<()>) -> io::Result<()> {
+        let mut entities = self.lock()?;
+        let mut updated = entities.clone();
+        apply(&mut updated)?;
+        *entities = updated;
+        Ok(())
+    }
+
+    // Refuses: a backup covers the whole store, but an in-memory store holds only one collection and
+    // can't reach the others.
+    fn backup_to(&self, _dir: &Path) -> io::Result<Vec<String>> {
+        Err(single_collection(T::COLLECTION))
+    }
+
+    // Refuses, since no complete backup can have been taken of an in-memory store.
+    fn restore_from(&self, _dir: &Path) -> io::Result<()> {
+        Err(single_collection(T::COLLECTION))
+    }
+}
+
+// The error for backups and restores, which an in-memory store of one collection can't provide.
+fn single_collection(collection: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("an in-memory store holds only its {} and can't back up or restore the whole store", collection),
+    )
+}
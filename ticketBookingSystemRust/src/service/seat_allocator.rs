@@ -0,0 +1,106 @@
+// Imports the seat layout allocators choose from.
+use super::super::entities::seat_layout::{SeatKind, SeatLayout, SeatPosition};
+
+// Chooses the seats a booking's passengers are given. The booking service seats the passengers in
+// the order of the returned positions, which must all be free.
+pub trait SeatAllocator {
+    // Returns `count` free seats of `seats`, or None if they can't be found. Seats of the kind the
+    // passengers `prefer` should be chosen when possible, but a booking is never refused for want of
+    // them; the ticket reports passengers who didn't get their preference.
+    fn allocate(&self, seats: &SeatLayout, count: usize, prefer: Option<SeatKind>) -> Option<Vec<SeatPosition>>;
+}
+
+// Splits the free seats of `seats` into those of the preferred kind and the others, both in layout
+// order. Without a preference every free seat is preferred.
+fn free_seats(seats: &SeatLayout, prefer: Option<SeatKind>) -> (Vec<SeatPosition>, Vec<SeatPosition>) {
+    seats
+        .free_seats()
+        .into_iter()
+        .partition(|p| prefer.is_none_or(|kind| seats.seat_at(p).is_some_and(|s| s.kind == Some(kind))))
+}
+
+// Takes the first free seats in layout order: front coach first, row by row. This was the only
+// strategy before allocators could be chosen. Seats of the preferred kind are taken first.
+pub struct FillFrontToBack;
+
+impl SeatAllocator for FillFrontToBack {
+    fn allocate(&self, seats: &SeatLayout, count: usize, prefer: Option<SeatKind>) -> Option<Vec<SeatPosition>> {
+        let (preferred, others) = free_seats(seats, prefer);
+        let free: Vec<SeatPosition> = preferred.into_iter().chain(others).collect();
+        (free.len() >= count).then(|| free.into_iter().take(count).collect())
+    }
+}
+
+// Keeps a group together, trying in turn, front to back:
+//   1. adjacent seats in one row,
+//   2. any seats in one row,
+//   3. the fewest consecutive rows of one coach holding enough free seats,
+//   4. the first free seats anywhere, as FillFrontToBack.
+// With a preference, the group is placed among the free seats of that kind alone while enough of
+// them are left, as close together as they allow, and among every free seat otherwise. The default
+// allocator of the booking service.
+pub struct KeepTogether;
+
+impl SeatAllocator for KeepTogether {
+    fn allocate(&self, seats: &SeatLayout, count: usize, prefer: Option<SeatKind>) -> Option<Vec<SeatPosition>> {
+        if count == 0 {
+            return Some(Vec::new());
+        }
+        let (preferred, _) = free_seats(seats, prefer);
+        if preferred.len() >= count {
+            return Some(KeepTogether::place(preferred, count));
+        }
+        let free = seats.free_seats();
+        (free.len() >= count).then(|| KeepTogether::place(free, count))
+    }
+}
+
+impl KeepTogether {
+    // Places `count` passengers among `free`, free seats in layout order holding at least `count`.
+    fn place(free: Vec<SeatPosition>, count: usize) -> Vec<SeatPosition> {
+        // Free seats grouped by coach and row, in layout order.
+        let mut rows: Vec<Vec<SeatPosition>> = Vec::new();
+        for position in free.iter().cloned() {
+            match rows.last_mut() {
+                Some(row) if row[0].number.coach == position.number.coach && row[0].row == position.row => row.push(position),
+                _ => rows.push(vec![position]),
+            }
+        }
+        // 1. Adjacent seats in one row.
+        for row in &rows {
+            for run in row.windows(count) {
+                if run.windows(2).all(|pair| pair[1].column == pair[0].column + 1) {
+                    return run.to_vec();
+                }
+            }
+        }
+        // 2. Any seats in one row.
+        if let Some(row) = rows.iter().find(|row| row.len() >= count) {
+            return row[..count].to_vec();
+        }
+        // 3. The fewest consecutive rows of one coach.
+        let mut best: Option<(usize, &[Vec<SeatPosition>])> = None;
+        for start in 0..rows.len() {
+            let mut found = 0;
+            for end in start..rows.len() {
+                let (first, last) = (&rows[start][0], &rows[end][0]);
+                if last.number.coach != first.number.coach {
+                    break;
+                }
+                found += rows[end].len();
+                if found >= count {
+                    let span = last.row - first.row;
+                    if best.is_none_or(|(best_span, _)| span < best_span) {
+                        best = Some((span, &rows[start..=end]));
+                    }
+                    break;
+                }
+            }
+        }
+        if let Some((_, block)) = best {
+            return block.iter().flatten().take(count).cloned().collect();
+        }
+        // 4. Anywhere.
+        free.into_iter().take(count).collect()
+    }
+}
@@ -0,0 +1,641 @@
+// Imports HashMap to group seat rows by the train/vehicle they belong to, and HashSet to keep the
+// PNRs given to existing bookings unique.
+use std::collections::{HashMap, HashSet};
+// Imports io for the FileIO error type.
+use std::io;
+// Imports Path and PathBuf for the database location.
+use std::path::{Path, PathBuf};
+// Imports Duration for the busy timeout.
+use std::time::Duration;
+// Imports rusqlite, the embedded SQLite driver.
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+// Imports serde_json to map entities to table columns through their Serde representation.
+use serde_json::{Map, Value};
+// Imports the entities stored by this backend, and the PNR that seats are looked up by.
+use super::super::entities::pnr::Pnr;
+use super::super::entities::seat_layout::{Coach, Seat, SeatLayout};
+use super::super::entities::train::Train;
+use super::super::entities::user::User;
+use super::super::entities::vehicle::Vehicle;
+// Imports the storage trait implemented here and the JSON backend used by the importer.
+use super::file_io::{FileIO, FileIOImpl, Record, RecordChange, DEFAULT_LOCK_TIMEOUT, USERS};
+// Imports the PNR, seat kind and coach class derivations shared with the db.json migrations.
+use super::file_io::migrations::{legacy_pnr, row_seat_kind, LEGACY_COACH_CLASS};
+// Imports the storage configuration used by from_config.
+use super::config::StorageConfig;
+
+// Database path used by the SQLite backend when nothing else is configured.
+pub const DEFAULT_SQLITE_PATH: &str = "db.sqlite3";
+// Name of the database copy inside a backup directory.
+const BACKUP_FILE: &str = "db.sqlite3";
+// Schema version stored in `PRAGMA user_version`; bump it together with a change to SCHEMA and an
+// upgrade step in `upgrade`.
+pub const SQLITE_SCHEMA_VERSION: i64 = 4;
+
+// Tables of the SQLite backend. `users` is the user registry. Trains and vehicles have one table each,
+// keyed by their id; every seat of their layouts is a row of `seats`, pointing at the passenger in
+// `users` (NULL while free), holding the PNR of its booking and the seat's kind; the class of its coach
+// is repeated on each of the coach's seats like the coach code. Seat rows are kept in layout order by
+// their coach, row and column indexes.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS users (
+        user_id     TEXT PRIMARY KEY,
+        name        TEXT NOT NULL,
+        aadhar_card TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trains (
+        id          TEXT PRIMARY KEY,
+        name        TEXT NOT NULL,
+        source      TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        time        INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS vehicles (
+        id          TEXT PRIMARY KEY,
+        name        TEXT NOT NULL,
+        source      TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        time        INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS seats (
+        collection   TEXT NOT NULL,
+        entity_id    TEXT NOT NULL,
+        coach_index  INTEGER NOT NULL,
+        coach        TEXT NOT NULL,
+        class        TEXT NOT NULL,
+        row_index    INTEGER NOT NULL,
+        column_index INTEGER NOT NULL,
+        number       INTEGER NOT NULL,
+        user_id      TEXT REFERENCES users(user_id),
+        pnr          TEXT,
+        kind         TEXT,
+        PRIMARY KEY (collection, entity_id, coach_index, row_index, column_index)
+    );
+    CREATE INDEX IF NOT EXISTS seats_by_user ON seats(user_id);
+    CREATE INDEX IF NOT EXISTS seats_by_pnr ON seats(pnr);
+";
+
+// Summarises an import from db.json.
+#[derive(Debug)]
+pub struct ImportReport {
+    pub users: usize,    // Registered users copied into the SQLite database.
+    pub trains: usize,   // Trains copied into the SQLite database.
+    pub vehicles: usize, // Vehicles copied into the SQLite database.
+}
+
+// Stores entities in an SQLite database file, implementing the same FileIO trait as the JSON backend.
+// Each call opens its own connection; SQLite's own locking keeps concurrent processes consistent.
+pub struct SqliteIOImpl<T> {
+    path: PathBuf,                         // Path of the SQLite database file.
+    busy_timeout: Duration,                // How long to wait for another process's write transaction.
+    _phantom: std::marker::PhantomData<T>, // PhantomData ensures type safety for the generic T.
+}
+
+impl<T> SqliteIOImpl<T> {
+    // Creates a SqliteIOImpl using "db.sqlite3" in the current directory.
+    pub fn new() -> Self {
+        Self::with_path(DEFAULT_SQLITE_PATH)
+    }
+
+    // Creates a SqliteIOImpl storing its data at `path`.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        SqliteIOImpl {
+            path: path.into(),
+            busy_timeout: DEFAULT_LOCK_TIMEOUT,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    // Creates a SqliteIOImpl using the database path and lock timeout from a StorageConfig.
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self::with_path(config.database_path()).with_busy_timeout(config.lock_timeout)
+    }
+
+    // Sets how long operations wait for a write transaction held by another process.
+    pub fn with_busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = timeout;
+        self
+    }
+
+    // Returns the path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Opens a connection, creating the tables on first use. The schema version is checked without a
+    // transaction; only a database that still has to be created or upgraded takes the write lock.
+    fn connect(&self) -> io::Result<Connection> {
+        let mut conn = Connection::open(&self.path).map_err(sql_error)?;
+        conn.busy_timeout(self.busy_timeout).map_err(sql_error)?;
+        conn.pragma_update(None, "foreign_keys", "ON").map_err(sql_error)?;
+        // Write-ahead logging lets readers continue while a booking is being written.
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(())).map_err(sql_error)?;
+        if self.schema_version(&conn)? == SQLITE_SCHEMA_VERSION {
+            return Ok(conn);
+        }
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        // Another process may have created or upgraded the tables while this one waited for the lock.
+        let version = self.schema_version(&tx)?;
+        if version < SQLITE_SCHEMA_VERSION {
+            if version == 0 {
+                tx.execute_batch(SCHEMA).map_err(sql_error)?;
+            } else {
+                upgrade(&tx, version)?;
+            }
+            tx.pragma_update(None, "user_version", SQLITE_SCHEMA_VERSION).map_err(sql_error)?;
+        }
+        tx.commit().map_err(sql_error)?;
+        Ok(conn)
+    }
+
+    // Returns the schema version of the database, refusing one newer than this program supports.
+    fn schema_version(&self, conn: &Connection) -> io::Result<i64> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(sql_error)?;
+        if version > SQLITE_SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{} has schema version {}, newer than this program supports ({})",
+                    self.path.display(), version, SQLITE_SCHEMA_VERSION
+                ),
+            ));
+        }
+        Ok(version)
+    }
+}
+
+// Implements Default for SqliteIOImpl, delegating to new().
+impl<T> Default for SqliteIOImpl<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Upgrades the tables of a database created at schema version `version` to SQLITE_SCHEMA_VERSION.
+fn upgrade(conn: &Connection, version: i64) -> io::Result<()> {
+    if version < 2 {
+        // Version 2 stores a PNR with every booked seat. A user's seats on one train/vehicle were a
+        // single booking and get the PNR the db.json migration would give them.
+        conn.execute_batch("ALTER TABLE seats ADD COLUMN pnr TEXT; CREATE INDEX seats_by_pnr ON seats(pnr);")
+            .map_err(sql_error)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT collection, entity_id, user_id FROM seats WHERE user_id IS NOT NULL
+                 ORDER BY collection, entity_id, user_id",
+            )
+            .map_err(sql_error)?;
+        let bookings = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+            .map_err(sql_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+        let mut taken: HashMap<String, HashSet<String>> = HashMap::new();
+        for (collection, entity_id, user_id) in bookings {
+            let pnr = legacy_pnr(&collection, &entity_id, &user_id, taken.entry(collection.clone()).or_default());
+            conn.execute(
+                "UPDATE seats SET pnr = ?1 WHERE collection = ?2 AND entity_id = ?3 AND user_id = ?4",
+                params![pnr, collection, entity_id, user_id],
+            )
+            .map_err(sql_error)?;
+        }
+    }
+    if version < 3 {
+        // Version 3 stores every seat's kind. Layouts were rows of seats, so it follows from the
+        // seat's place in its row, as in the db.json migration.
+        conn.execute_batch("ALTER TABLE seats ADD COLUMN kind TEXT;").map_err(sql_error)?;
+        let mut stmt = conn
+            .prepare("SELECT collection, entity_id, coach_index, row_index, COUNT(*) FROM seats GROUP BY 1, 2, 3, 4")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?, row.get::<_, i64>(4)?))
+            })
+            .map_err(sql_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+        for (collection, entity_id, coach_index, row_index, width) in rows {
+            for column in 0..width {
+                conn.execute(
+                    "UPDATE seats SET kind = ?1 WHERE collection = ?2 AND entity_id = ?3 AND coach_index = ?4
+                         AND row_index = ?5 AND column_index = ?6",
+                    params![row_seat_kind(column as usize, width as usize), collection, entity_id, coach_index, row_index, column],
+                )
+                .map_err(sql_error)?;
+            }
+        }
+    }
+    if version < 4 {
+        // Version 4 stores the class of every coach; existing coaches, rows of seats, become Chair
+        // Car coaches, as in the db.json migration.
+        conn.execute(&format!("ALTER TABLE seats ADD COLUMN class TEXT NOT NULL DEFAULT '{}'", LEGACY_COACH_CLASS), [])
+            .map_err(sql_error)?;
+    }
+    Ok(())
+}
+
+// Converts SQLite errors to io::Error: a database held busy past the timeout becomes TimedOut
+// (reported as a lock timeout), data that doesn't fit the entities becomes InvalidData.
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DatabaseBusy) | Some(rusqlite::ErrorCode::DatabaseLocked) => {
+            io::Error::new(io::ErrorKind::TimedOut, e)
+        }
+        Some(rusqlite::ErrorCode::NotADatabase) => io::Error::new(io::ErrorKind::InvalidData, e),
+        _ => match e {
+            rusqlite::Error::FromSqlConversionFailure(..) | rusqlite::Error::InvalidColumnType(..) => {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            }
+            _ => io::Error::other(e),
+        },
+    }
+}
+
+// Returns a text field of an entity's Serde representation.
+fn text_field<'a>(value: &'a Value, key: &str) -> io::Result<&'a str> {
+    value[key]
+        .as_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("entity field `{}` is not text", key)))
+}
+
+// Reads every registered user, in registration order, or only the one with the given id.
+fn read_users<T: Record>(conn: &Connection, id: Option<&str>) -> io::Result<Vec<T>> {
+    let filter = if id.is_some() { "WHERE user_id = ?1" } else { "" };
+    let mut stmt = conn
+        .prepare(&format!("SELECT user_id, name, aadhar_card FROM users {} ORDER BY rowid", filter))
+        .map_err(sql_error)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(id)).map_err(sql_error)?;
+    let mut users = Vec::new();
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let mut value = Map::new();
+        value.insert(T::ID_KEY.to_string(), Value::String(row.get(0).map_err(sql_error)?));
+        value.insert("name".to_string(), Value::String(row.get(1).map_err(sql_error)?));
+        value.insert("aadharCard".to_string(), Value::String(row.get(2).map_err(sql_error)?));
+        users.push(serde_json::from_value(Value::Object(value))?);
+    }
+    Ok(users)
+}
+
+// Inserts or replaces one registered user.
+fn write_user<T: Record>(conn: &Connection, user: &T) -> io::Result<()> {
+    let value = serde_json::to_value(user)?;
+    conn.execute(
+        "INSERT INTO users (user_id, name, aadhar_card) VALUES (?1, ?2, ?3)
+         ON CONFLICT(user_id) DO UPDATE SET name = excluded.name, aadhar_card = excluded.aadhar_card",
+        params![user.record_id(), text_field(&value, "name")?, text_field(&value, "aadharCard")?],
+    )
+    .map_err(sql_error)?;
+    Ok(())
+}
+
+// Reads every entity of T's table, with their seat layouts, in insertion order, or only the one with
+// the given id, reading just its own seat rows.
+fn read_entities<T: Record>(conn: &Connection, id: Option<&str>) -> io::Result<Vec<T>> {
+    if T::COLLECTION == USERS {
+        return read_users(conn, id);
+    }
+    let mut layouts: HashMap<String, Vec<Coach>> = HashMap::new();
+    let filter = if id.is_some() { "AND entity_id = ?2" } else { "" };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT entity_id, coach_index, coach, row_index, number, user_id, pnr, kind, class FROM seats
+             WHERE collection = ?1 {}
+             ORDER BY entity_id, coach_index, row_index, column_index",
+            filter
+        ))
+        .map_err(sql_error)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter([T::COLLECTION].into_iter().chain(id))).map_err(sql_error)?;
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let coaches = layouts.entry(row.get(0).map_err(sql_error)?).or_default();
+        // Rows arrive in layout order, so a new index means the next coach or row starts here.
+        let coach_index: i64 = row.get(1).map_err(sql_error)?;
+        if coaches.len() as i64 <= coach_index {
+            let class = serde_json::from_value(Value::String(row.get(8).map_err(sql_error)?))?;
+            coaches.push(Coach { code: row.get(2).map_err(sql_error)?, class, rows: Vec::new() });
+        }
+        let coach = coaches.last_mut().expect("a coach was just pushed");
+        let row_index: i64 = row.get(3).map_err(sql_error)?;
+        if coach.rows.len() as i64 <= row_index {
+            coach.rows.push(Vec::new());
+        }
+        let seat = Seat {
+            number: row.get(4).map_err(sql_error)?,
+            passenger: row.get(5).map_err(sql_error)?,
+            pnr: row.get::<_, Option<String>>(6).map_err(sql_error)?.map(|pnr| pnr.as_str().into()),
+            kind: row.get::<_, Option<String>>(7).map_err(sql_error)?.map(|kind| serde_json::from_value(Value::String(kind))).transpose()?,
+        };
+        coach.rows.last_mut().expect("a row was just pushed").push(seat);
+    }
+
+    let filter = if id.is_some() { "WHERE id = ?1" } else { "" };
+    let mut stmt = conn
+        .prepare(&format!("SELECT id, name, source, destination, time FROM {} {} ORDER BY rowid", T::COLLECTION, filter))
+        .map_err(sql_error)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(id)).map_err(sql_error)?;
+    let mut entities = Vec::new();
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let id: String = row.get(0).map_err(sql_error)?;
+        let seats = SeatLayout { coaches: layouts.remove(&id).unwrap_or_default() };
+        // Rebuilds the entity's Serde representation, so any Record with these fields can be read.
+        let mut value = Map::new();
+        value.insert(T::ID_KEY.to_string(), Value::String(id));
+        value.insert("name".to_string(), Value::String(row.get(1).map_err(sql_error)?));
+        value.insert("source".to_string(), Value::String(row.get(2).map_err(sql_error)?));
+        value.insert("destination".to_string(), Value::String(row.get(3).map_err(sql_error)?));
+        value.insert("time".to_string(), Value::from(row.get::<_, i64>(4).map_err(sql_error)?));
+        value.insert("seats".to_string(), serde_json::to_value(seats)?);
+        entities.push(serde_json::from_value(Value::Object(value))?);
+    }
+    Ok(entities)
+}
+
+// Inserts or replaces the table row of one entity, leaving its seats alone, and returns its layout.
+fn write_entity_row<T: Record>(conn: &Connection, entity: &T) -> io::Result<SeatLayout> {
+    let mut value = serde_json::to_value(entity)?;
+    let seats: SeatLayout = serde_json::from_value(value["seats"].take())?;
+    let id = entity.record_id();
+    conn.execute(
+        &format!(
+            "INSERT INTO {} (id, name, source, destination, time) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, source = excluded.source,
+                 destination = excluded.destination, time = excluded.time",
+            T::COLLECTION
+        ),
+        params![
+            id,
+            text_field(&value, "name")?,
+            text_field(&value, "source")?,
+            text_field(&value, "destination")?,
+            value["time"].as_i64().unwrap_or_default(),
+        ],
+    )
+    .map_err(sql_error)?;
+    Ok(seats)
+}
+
+// Inserts or replaces one entity and all of its seat rows. The passengers must already be registered
+// in `users`.
+fn write_entity<T: Record>(conn: &Connection, entity: &T) -> io::Result<()> {
+    if T::COLLECTION == USERS {
+        return write_user(conn, entity);
+    }
+    let seats = write_entity_row(conn, entity)?;
+    let id = entity.record_id();
+    conn.execute("DELETE FROM seats WHERE collection = ?1 AND entity_id = ?2", params![T::COLLECTION, id])
+        .map_err(sql_error)?;
+    let mut insert_seat = conn
+        .prepare_cached(
+            "INSERT INTO seats (collection, entity_id, coach_index, coach, class, row_index, column_index, number, user_id, pnr, kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )
+        .map_err(sql_error)?;
+    for (coach_index, coach) in seats.coaches.iter().enumerate() {
+        for (row_index, row) in coach.rows.iter().enumerate() {
+            for (column_index, seat) in row.iter().enumerate() {
+                let kind = seat.kind.map(serde_json::to_value).transpose()?;
+                insert_seat
+                    .execute(params![
+                        T::COLLECTION,
+                        id,
+                        coach_index as i64,
+                        coach.code,
+                        coach.class.code(),
+                        row_index as i64,
+                        column_index as i64,
+                        seat.number,
+                        seat.passenger,
+                        seat.pnr.as_ref().map(|pnr| pnr.as_str()),
+                        kind.as_ref().and_then(Value::as_str),
+                    ])
+                    .map_err(sql_error)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Writes the changes from `before` to `after`, two versions of one entity. While the layout keeps its
+// shape, as it does for bookings and cancellations, only the seat rows whose passenger or PNR changed
+// are updated; otherwise the entity is rewritten with all of its seats.
+fn write_entity_changes<T: Record>(conn: &Connection, before: &T, after: &T) -> io::Result<()> {
+    let (Some(old), Some(new)) = (before.seats(), after.seats()) else {
+        return write_entity(conn, after);
+    };
+    if !same_shape(old, new) {
+        return write_entity(conn, after);
+    }
+    write_entity_row(conn, after)?;
+    let mut update_seat = conn
+        .prepare_cached(
+            "UPDATE seats SET user_id = ?1, pnr = ?2
+             WHERE collection = ?3 AND entity_id = ?4 AND coach_index = ?5 AND row_index = ?6 AND column_index = ?7",
+        )
+        .map_err(sql_error)?;
+    for (coach_index, (old_coach, new_coach)) in old.coaches.iter().zip(&new.coaches).enumerate() {
+        for (row_index, (old_row, new_row)) in old_coach.rows.iter().zip(&new_coach.rows).enumerate() {
+            for (column_index, (old_seat, seat)) in old_row.iter().zip(new_row).enumerate() {
+                if old_seat.passenger == seat.passenger && old_seat.pnr == seat.pnr {
+                    continue;
+                }
+                update_seat
+                    .execute(params![
+                        seat.passenger,
+                        seat.pnr.as_ref().map(|pnr| pnr.as_str()),
+                        T::COLLECTION,
+                        after.record_id(),
+                        coach_index as i64,
+                        row_index as i64,
+                        column_index as i64,
+                    ])
+                    .map_err(sql_error)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns true if two layouts have the same coaches, rows and seats, whoever sits on them.
+fn same_shape(a: &SeatLayout, b: &SeatLayout) -> bool {
+    a.coaches.len() == b.coaches.len()
+        && a.coaches.iter().zip(&b.coaches).all(|(a, b)| {
+            a.code == b.code
+                && a.class == b.class
+                && a.rows.len() == b.rows.len()
+                && a.rows.iter().zip(&b.rows).all(|(a, b)| {
+                    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.number == b.number && a.kind == b.kind)
+                })
+        })
+}
+
+// Deletes one entity and its seats, returning whether it existed. A user can only be deleted while
+// no seat refers to them.
+fn delete_entity<T: Record>(conn: &Connection, id: &str) -> io::Result<bool> {
+    if T::COLLECTION == USERS {
+        return Ok(conn.execute("DELETE FROM users WHERE user_id = ?1", [id]).map_err(sql_error)? > 0);
+    }
+    conn.execute("DELETE FROM seats WHERE collection = ?1 AND entity_id = ?2", params![T::COLLECTION, id])
+        .map_err(sql_error)?;
+    let removed = conn
+        .execute(&format!("DELETE FROM {} WHERE id = ?1", T::COLLECTION), [id])
+        .map_err(sql_error)?;
+    Ok(removed > 0)
+}
+
+// Implements FileIO for every Record type stored in SQLite. Each operation is one transaction.
+impl<T: Record> FileIO for SqliteIOImpl<T> {
+    type Item = T;
+
+    // Saves an entity, replacing the stored one with the same id.
+    fn save_to_file(&self, entity: &T) -> io::Result<()> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        write_entity(&tx, entity)?;
+        tx.commit().map_err(sql_error)
+    }
+
+    // Reads every entity of this type.
+    fn read_from_file(&self) -> io::Result<Vec<T>> {
+        let mut conn = self.connect()?;
+        // A read transaction gives a consistent view of entities and seats.
+        let tx = conn.transaction().map_err(sql_error)?;
+        read_entities(&tx, None)
+    }
+
+    // Reads the entity with the given id and only its own seat rows.
+    fn read_record(&self, id: &str) -> io::Result<Option<T>> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction().map_err(sql_error)?;
+        Ok(read_entities(&tx, Some(id))?.pop())
+    }
+
+    // Finds the entity holding the PNR through the seats_by_pnr index, then reads only that entity.
+    fn read_record_with_pnr(&self, pnr: &Pnr) -> io::Result<Option<T>> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction().map_err(sql_error)?;
+        match entity_with_pnr::<T>(&tx, pnr)? {
+            Some(id) => Ok(read_entities(&tx, Some(&id))?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    // Deletes the entity with the given id.
+    fn delete_from_file(&self, id: &str) -> io::Result<bool> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        let removed = delete_entity::<T>(&tx, id)?;
+        tx.commit().map_err(sql_error)?;
+        Ok(removed)
+    }
+
+    // Applies `apply` inside a write transaction, so concurrent processes are serialised. Only the
+    // entities that `apply` added, changed or removed are written back.
+    fn update_in_file(&self, apply: &mut dyn FnMut(&mut Vec<T>) -> io::Result<()>) -> io::Result<()> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        let mut entities = read_entities::<T>(&tx, None)?;
+        let before: HashMap<String, (T, Value)> = entities
+            .iter()
+            .map(|e| Ok((e.record_id().to_string(), (e.clone(), serde_json::to_value(e)?))))
+            .collect::<io::Result<_>>()?;
+        apply(&mut entities)?; // Dropping the transaction on error rolls everything back.
+        for entity in &entities {
+            match before.get(entity.record_id()) {
+                Some((_, value)) if *value == serde_json::to_value(entity)? => {}
+                Some((old, _)) => write_entity_changes(&tx, old, entity)?,
+                None => write_entity(&tx, entity)?,
+            }
+        }
+        for id in before.keys() {
+            if !entities.iter().any(|e| e.record_id() == id) {
+                delete_entity::<T>(&tx, id)?;
+            }
+        }
+        tx.commit().map_err(sql_error)
+    }
+
+    // Applies `apply` to one entity inside a write transaction, reading and writing only its rows;
+    // whether a PNR is taken is looked up in the seats_by_pnr index.
+    fn update_record(&self, id: &str, apply: &mut RecordChange<T>) -> io::Result<()> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        let before = read_entities::<T>(&tx, Some(id))?.pop();
+        let mut entity = before.clone();
+        // The lookup only fails if the database does; treating that as taken just draws another PNR,
+        // and the failure resurfaces when the change is written.
+        let taken = |pnr: &Pnr| !matches!(entity_with_pnr::<T>(&tx, pnr), Ok(None));
+        apply(&mut entity, &taken)?;
+        match (&before, &entity) {
+            (Some(old), Some(new)) if serde_json::to_value(old)? != serde_json::to_value(new)? => {
+                write_entity_changes(&tx, old, new)?
+            }
+            (Some(_), Some(_)) => {}
+            (None, Some(new)) => write_entity(&tx, new)?,
+            (Some(_), None) => {
+                delete_entity::<T>(&tx, id)?;
+            }
+            (None, None) => {}
+        }
+        tx.commit().map_err(sql_error)
+    }
+
+    // Copies the database with SQLite's online backup, which yields a consistent snapshot even while
+    // other processes keep booking.
+    fn backup_to(&self, dir: &Path) -> io::Result<Vec<String>> {
+        let conn = self.connect()?;
+        conn.backup("main", dir.join(BACKUP_FILE), None).map_err(sql_error)?;
+        Ok(vec![BACKUP_FILE.to_string()])
+    }
+
+    // Overwrites the database with the backed-up copy through SQLite, so open connections of other
+    // processes see either the old or the restored contents.
+    fn restore_from(&self, dir: &Path) -> io::Result<()> {
+        let source = dir.join(BACKUP_FILE);
+        if !source.is_file() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} is missing", source.display())));
+        }
+        let mut conn = self.connect()?;
+        conn.restore("main", source, None::<fn(rusqlite::backup::Progress)>).map_err(sql_error)
+    }
+}
+
+// Returns the id of the entity of T's collection holding a seat booked under `pnr`.
+fn entity_with_pnr<T: Record>(conn: &Connection, pnr: &Pnr) -> io::Result<Option<String>> {
+    conn.query_row(
+        "SELECT entity_id FROM seats WHERE pnr = ?1 AND collection = ?2 LIMIT 1",
+        params![pnr.as_str(), T::COLLECTION],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(sql_error)
+}
+
+// Copies every user, train and vehicle of a db.json file into an SQLite database in one transaction.
+// Records already in the SQLite database are replaced by id, so the import can be re-run safely.
+pub fn import_json(json_path: &Path, sqlite: &SqliteIOImpl<Vehicle>) -> io::Result<ImportReport> {
+    let users = FileIOImpl::<User>::with_path(json_path).read_from_file()?;
+    let trains = FileIOImpl::<Train>::with_path(json_path).read_from_file()?;
+    let vehicles = FileIOImpl::<Vehicle>::with_path(json_path).read_from_file()?;
+    let mut conn = sqlite.connect()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+    for user in &users {
+        write_user(&tx, user)?; // Users first, since seats refer to them.
+    }
+    for train in &trains {
+        write_entity(&tx, train)?;
+    }
+    for vehicle in &vehicles {
+        write_entity(&tx, vehicle)?;
+    }
+    tx.commit().map_err(sql_error)?;
+    Ok(ImportReport { users: users.len(), trains: trains.len(), vehicles: vehicles.len() })
+}
+
+// Returns true if the SQLite database already holds a user, train or vehicle, so the one-shot import
+// can refuse to merge into a database that is in use.
+pub fn has_entities(sqlite: &SqliteIOImpl<Vehicle>) -> io::Result<bool> {
+    let conn = sqlite.connect()?;
+    let any = conn
+        .query_row("SELECT 1 FROM users UNION ALL SELECT 1 FROM trains UNION ALL SELECT 1 FROM vehicles LIMIT 1", [], |_| Ok(()))
+        .optional()
+        .map_err(sql_error)?;
+    Ok(any.is_some())
+}
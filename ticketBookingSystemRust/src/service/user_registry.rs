@@ -0,0 +1,128 @@
+// Imports io for the error raised when stored details can't be compared.
+use std::io;
+// Imports the User struct kept in the registry.
+use super::super::entities::user::User;
+// Imports the storage trait holding the "users" collection.
+use super::file_io::FileIO;
+// Imports the error type shared with the booking service.
+use super::booking_error::BookingError;
+
+// The registry of passengers, persisted as the "users" collection next to the trains and vehicles.
+// Every user is stored once, keyed by user_id; seats only hold the id, so a change of name is made
+// in one place.
+pub struct UserRegistry {
+    storage: Box<dyn FileIO<Item = User>>, // The storage backend holding the users.
+}
+
+impl UserRegistry {
+    // Creates a registry over the given storage backend.
+    pub fn new(storage: Box<dyn FileIO<Item = User>>) -> Self {
+        UserRegistry { storage }
+    }
+
+    // Registers the user if the id is new and returns the registered details. A known id is accepted
+    // when the Aadhar number matches (the registered name is kept; see rename) and refused with
+    // UserConflict when it doesn't, so one id can't be shared by two people.
+    pub fn register(&self, user: &User) -> Result<User, BookingError> {
+        let mut registered = self.register_all(std::slice::from_ref(user))?;
+        Ok(registered.remove(0))
+    }
+
+    // Registers several users the way register does, in one update: if any of them is refused, none
+    // is registered. Returns the registered details in the order given.
+    pub fn register_all(&self, users: &[User]) -> Result<Vec<User>, BookingError> {
+        Ok(self.insert_all(users)?.0)
+    }
+
+    // Registers several users the way register_all does and returns the ids of those that were not
+    // registered before, so a booking that fails afterwards can unregister them again.
+    pub fn register_new(&self, users: &[User]) -> Result<Vec<String>, BookingError> {
+        Ok(self.insert_all(users)?.1)
+    }
+
+    // Removes the users with the given ids, e.g. the passengers register_new added for a booking that
+    // was then refused. Ids that aren't registered are skipped.
+    pub fn unregister(&self, user_ids: &[String]) -> Result<(), BookingError> {
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+        self.storage.update_in_file(&mut |users| {
+            users.retain(|u| !user_ids.contains(&u.user_id));
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    // Registers the new ones of `users` in one update, returning the registered details of all of
+    // them in the order given and the ids of the new ones.
+    fn insert_all(&self, users: &[User]) -> Result<(Vec<User>, Vec<String>), BookingError> {
+        let mut registered = Vec::new();
+        let mut added = Vec::new();
+        self.storage.update_in_file(&mut |stored| {
+            registered.clear();
+            added.clear();
+            for user in users {
+                match registered_as(stored, user)? {
+                    Some(existing) => registered.push(existing),
+                    None => {
+                        stored.push(user.clone());
+                        registered.push(user.clone());
+                        added.push(user.user_id.clone());
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok((registered, added))
+    }
+
+    // Returns the registered user with the given id.
+    pub fn get(&self, user_id: &str) -> Result<User, BookingError> {
+        self.storage
+            .read_from_file()?
+            .into_iter()
+            .find(|u| u.user_id == user_id)
+            .ok_or(BookingError::NotFound { kind: "User", id: user_id.to_string() })
+    }
+
+    // Returns every registered user, in registration order.
+    pub fn all(&self) -> Result<Vec<User>, BookingError> {
+        Ok(self.storage.read_from_file()?)
+    }
+
+    // Changes the name of a registered user; every booking of theirs shows the new name.
+    pub fn rename(&self, user_id: &str, name: String) -> Result<(), BookingError> {
+        if name.trim().is_empty() {
+            return Err(BookingError::Validation("name must not be empty".to_string()));
+        }
+        self.storage.update_in_file(&mut |users| {
+            let user = users
+                .iter_mut()
+                .find(|u| u.user_id == user_id)
+                .ok_or_else(|| BookingError::NotFound { kind: "User", id: user_id.to_string() }.into_io_error())?;
+            user.name = name.clone();
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+// Returns the registered details of `user` among `stored`, None if the id is new, or an error if the
+// user's Aadhar number is invalid, or the id belongs to someone else or its Aadhar number can't be
+// compared.
+fn registered_as(stored: &[User], user: &User) -> io::Result<Option<User>> {
+    if let Err(e) = user.aadhar_card.validate() {
+        let message = format!("Aadhar number of user {} {}", user.user_id, e);
+        return Err(BookingError::Validation(message).into_io_error());
+    }
+    match stored.iter().find(|u| u.user_id == user.user_id) {
+        Some(existing) if existing.aadhar_card.is_encrypted() => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("the Aadhar number of user {} is encrypted; configure the key to book for them", user.user_id),
+        )),
+        Some(existing) if existing.aadhar_card != user.aadhar_card => {
+            Err(BookingError::UserConflict { user_id: user.user_id.clone() }.into_io_error())
+        }
+        existing => Ok(existing.cloned()),
+    }
+}
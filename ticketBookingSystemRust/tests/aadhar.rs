@@ -0,0 +1,52 @@
+// Tests for Aadhar number validation and masking.
+use train_booking::entities::aadhar::{AadharError, AadharNumber};
+use train_booking::entities::user::User;
+
+#[test]
+fn accepts_numbers_with_a_valid_check_digit() {
+    for input in ["234123412346", "498765432102", "987654321012"] {
+        assert_eq!(AadharNumber::parse(input).unwrap().reveal(), input);
+    }
+}
+
+#[test]
+fn strips_group_separators() {
+    assert_eq!(AadharNumber::parse(" 2341 2341 2346 ").unwrap().reveal(), "234123412346");
+    assert_eq!(AadharNumber::parse("2341-2341-2346").unwrap().reveal(), "234123412346");
+}
+
+#[test]
+fn rejects_a_wrong_check_digit() {
+    assert_eq!(AadharNumber::parse("234123412345").unwrap_err(), AadharError::ChecksumMismatch);
+    // Swapping two adjacent digits is caught as well.
+    assert_eq!(AadharNumber::parse("234123412364").unwrap_err(), AadharError::ChecksumMismatch);
+}
+
+#[test]
+fn rejects_malformed_input() {
+    assert_eq!(AadharNumber::parse("1236986t89420t").unwrap_err(), AadharError::NotDigits);
+    assert_eq!(AadharNumber::parse("23412341234").unwrap_err(), AadharError::WrongLength(11));
+    assert_eq!(AadharNumber::parse("").unwrap_err(), AadharError::WrongLength(0));
+    assert_eq!(AadharNumber::parse("134123412346").unwrap_err(), AadharError::InvalidFirstDigit);
+    assert_eq!(AadharNumber::parse("034123412346").unwrap_err(), AadharError::InvalidFirstDigit);
+}
+
+#[test]
+fn stored_values_load_unchecked_and_can_be_flagged() {
+    let user: User = serde_json::from_str(r#"{"userId":"u1","name":"Asha","aadharCard":"1236986t89420t"}"#).unwrap();
+    assert!(!user.aadhar_card.is_valid());
+    assert_eq!(user.reveal_aadhar(), "1236986t89420t");
+    assert_eq!(serde_json::to_value(&user).unwrap()["aadharCard"], "1236986t89420t");
+
+    let encrypted: AadharNumber = serde_json::from_str(r#""enc:v1:0123abcd:AAAA""#).unwrap();
+    assert_eq!(encrypted.validate().unwrap_err(), AadharError::Encrypted);
+}
+
+#[test]
+fn display_and_debug_mask_all_but_the_last_four_digits() {
+    let aadhar = AadharNumber::parse("234123412346").unwrap();
+    assert_eq!(aadhar.to_string(), "XXXX XXXX 2346");
+    assert!(!format!("{:?}", aadhar).contains("23412341"));
+    let encrypted: AadharNumber = serde_json::from_str(r#""enc:v1:0123abcd:AAAA""#).unwrap();
+    assert_eq!(encrypted.to_string(), "XXXX XXXX XXXX");
+}
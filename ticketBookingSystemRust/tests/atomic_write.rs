@@ -0,0 +1,58 @@
+// Tests for the crash-safe write path used by FileIOImpl::save_to_file.
+use std::fs;
+use std::io::{self, Write};
+use train_booking::service::file_io::{temp_path_for, write_atomically};
+
+#[test]
+fn replaces_contents_and_leaves_no_temp_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, "[1]").unwrap();
+
+    write_atomically(&path, |f| f.write_all(b"[1, 2]")).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[1, 2]");
+    assert!(!temp_path_for(&path).exists());
+}
+
+#[test]
+fn interrupted_write_keeps_previous_contents() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, "[\"old\"]").unwrap();
+
+    // Simulates a crash or full disk halfway through writing the new database.
+    let result = write_atomically(&path, |f| {
+        f.write_all(b"[\"new\", \"part")?;
+        Err(io::Error::other("disk full"))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[\"old\"]");
+    assert!(!temp_path_for(&path).exists());
+}
+
+#[test]
+fn leftover_temp_file_from_crash_is_ignored_and_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, "[\"old\"]").unwrap();
+    // A process killed before the rename leaves a partial temp file behind.
+    fs::write(temp_path_for(&path), "[\"half-writ").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[\"old\"]");
+
+    write_atomically(&path, |f| f.write_all(b"[\"new\"]")).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[\"new\"]");
+    assert!(!temp_path_for(&path).exists());
+}
+
+#[test]
+fn creates_missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+
+    write_atomically(&path, |f| f.write_all(b"[]")).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+}
@@ -0,0 +1,172 @@
+// Tests for taking, verifying and restoring backups, and for the backups and ids that are refused.
+use std::fs;
+use std::io::ErrorKind;
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::pnr::Pnr;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::SeatLayout;
+use train_booking::entities::user::User;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::backup::{self, MANIFEST_FILE};
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+use train_booking::service::seat_allocator::{FillFrontToBack, SeatAllocator};
+use train_booking::service::memory_io::MemoryIOImpl;
+use train_booking::service::sqlite_io::SqliteIOImpl;
+
+fn vehicle(id: &str) -> Vehicle {
+    Vehicle {
+        vehicle_id: id.to_string(),
+        name: "Express".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats: SeatLayout::new(SeatClass::ChairCar, 1, 2, 4),
+    }
+}
+
+// Seats `user_ids` under one new PNR where a front-to-back booking would put them.
+fn occupy(seats: &mut SeatLayout, user_ids: &[&str]) {
+    let positions = FillFrontToBack.allocate(seats, user_ids.len(), None).unwrap();
+    seats.assign_at(&positions, user_ids, &Pnr::generate(|_| false)).unwrap();
+}
+
+fn ids(storage: &dyn FileIO<Item = Vehicle>) -> Vec<String> {
+    storage.read_from_file().unwrap().into_iter().map(|v| v.vehicle_id).collect()
+}
+
+#[test]
+fn a_json_backup_is_listed_verified_and_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = FileIOImpl::<Vehicle>::with_path(dir.path().join("db.json"));
+    storage.save_to_file(&vehicle("v1")).unwrap();
+
+    let taken = backup::create_backup(&storage, "json", &backups).unwrap();
+
+    assert_eq!(taken.manifest.backend, "json");
+    assert!(taken.path.join(MANIFEST_FILE).is_file());
+    let listed = backup::list_backups(&backups).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, taken.id);
+    let found = backup::find_backup(&backups, &taken.id).unwrap();
+    assert!(backup::verify_backup(&found).unwrap().is_empty());
+
+    storage.save_to_file(&vehicle("v2")).unwrap();
+    storage.delete_from_file("v1").unwrap();
+    backup::restore_backup(&storage, "json", &found).unwrap();
+
+    assert_eq!(ids(&storage), vec!["v1"]);
+}
+
+#[test]
+fn a_damaged_backup_is_reported_and_not_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = FileIOImpl::<Vehicle>::with_path(dir.path().join("db.json"));
+    storage.save_to_file(&vehicle("v1")).unwrap();
+    let taken = backup::create_backup(&storage, "json", &backups).unwrap();
+    storage.save_to_file(&vehicle("v2")).unwrap();
+    let name = &taken.manifest.files[0].name;
+    let copy = taken.path.join(name);
+
+    // Same size, different contents.
+    let contents = fs::read_to_string(&copy).unwrap();
+    fs::write(&copy, contents.replace("v1", "v9")).unwrap();
+    assert_eq!(backup::verify_backup(&taken).unwrap(), vec![format!("{}: checksum mismatch", name)]);
+    let error = backup::restore_backup(&storage, "json", &taken).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(ids(&storage), vec!["v1", "v2"]);
+
+    fs::write(&copy, "{").unwrap();
+    assert!(backup::verify_backup(&taken).unwrap()[0].starts_with(&format!("{}: size is 1 bytes", name)));
+
+    fs::remove_file(&copy).unwrap();
+    assert_eq!(backup::verify_backup(&taken).unwrap(), vec![format!("{}: missing", name)]);
+    assert!(backup::restore_backup(&storage, "json", &taken).is_err());
+    assert_eq!(ids(&storage), vec!["v1", "v2"]);
+}
+
+#[test]
+fn a_backup_of_another_backend_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = FileIOImpl::<Vehicle>::with_path(dir.path().join("db.json"));
+    storage.save_to_file(&vehicle("v1")).unwrap();
+    let taken = backup::create_backup(&storage, "json", &backups).unwrap();
+
+    let sqlite = SqliteIOImpl::<Vehicle>::with_path(dir.path().join("db.sqlite3"));
+    let error = backup::restore_backup(&sqlite, "sqlite", &taken).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(ids(&sqlite).is_empty());
+}
+
+#[test]
+fn an_sqlite_backup_is_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = SqliteIOImpl::<Vehicle>::with_path(dir.path().join("db.sqlite3"));
+    let users = SqliteIOImpl::<User>::with_path(storage.path());
+    users.save_to_file(&User { user_id: "u1".to_string(), name: "Asha".to_string(), aadhar_card: AadharNumber::parse("234123412346").unwrap() }).unwrap();
+    let mut v1 = vehicle("v1");
+    occupy(&mut v1.seats, &["u1"]);
+    storage.save_to_file(&v1).unwrap();
+    let taken = backup::create_backup(&storage, "sqlite", &backups).unwrap();
+    storage.save_to_file(&vehicle("v2")).unwrap();
+
+    backup::restore_backup(&storage, "sqlite", &taken).unwrap();
+
+    assert_eq!(ids(&storage), vec!["v1"]);
+    assert_eq!(storage.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+}
+
+#[test]
+fn backups_taken_in_the_same_second_get_distinct_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = FileIOImpl::<Vehicle>::with_path(dir.path().join("db.json"));
+    storage.save_to_file(&vehicle("v1")).unwrap();
+
+    let first = backup::create_backup(&storage, "json", &backups).unwrap();
+    let second = backup::create_backup(&storage, "json", &backups).unwrap();
+
+    assert_ne!(first.id, second.id);
+    assert_eq!(backup::list_backups(&backups).unwrap().len(), 2);
+    assert_eq!(backup::find_backup(&backups, "missing").unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn backup_ids_outside_the_backups_directory_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let backups = dir.path().join("backups");
+    let storage = FileIOImpl::<Vehicle>::with_path(dir.path().join("db.json"));
+    storage.save_to_file(&vehicle("v1")).unwrap();
+    let taken = backup::create_backup(&storage, "json", &backups).unwrap();
+    // A backup-like directory next to the backups directory must not be reachable through an id.
+    fs::create_dir(dir.path().join("elsewhere")).unwrap();
+    fs::copy(taken.path.join(MANIFEST_FILE), dir.path().join("elsewhere").join(MANIFEST_FILE)).unwrap();
+
+    for id in ["../elsewhere", "..", ".", "", "a/b", "a\\b", "/tmp", &format!("{}/.", taken.id)] {
+        assert_eq!(backup::find_backup(&backups, id).unwrap_err().kind(), ErrorKind::InvalidInput, "{}", id);
+    }
+    assert!(backup::find_backup(&backups, &taken.id).is_ok());
+}
+
+#[test]
+fn an_in_memory_store_refuses_to_back_up_one_collection_as_the_whole_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = MemoryIOImpl::with_entities(vec![vehicle("v1")]);
+
+    let err = backup::create_backup(&storage, "memory", &dir.path().join("backups")).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(storage.restore_from(dir.path()).unwrap_err().kind(), ErrorKind::Unsupported);
+    assert!(backup::list_backups(&dir.path().join("backups")).unwrap().is_empty());
+}
+
+#[test]
+fn backup_ids_are_utc_timestamps() {
+    assert_eq!(backup::format_utc(0), "1970-01-01T00-00-00Z");
+    assert_eq!(backup::format_utc(1_700_000_000), "2023-11-14T22-13-20Z");
+    assert_eq!(backup::format_utc(951_782_400), "2000-02-29T00-00-00Z");
+}
@@ -0,0 +1,504 @@
+// Tests for the booking logic, run against the in-memory store so no db.json is touched, and against
+// each persistent backend in a temporary directory.
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::pnr::Pnr;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::{SeatKind, SeatLayout, SeatNumber};
+use train_booking::entities::train::Train;
+use train_booking::entities::user::User;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::booking_error::BookingError;
+use train_booking::service::booking_service::{BookingService, BookingServiceImpl};
+use train_booking::service::booking_request::{BookingRequest, MAX_PASSENGERS};
+use train_booking::service::booking_view::Ticket;
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+use train_booking::service::journal_io::JournalIOImpl;
+use train_booking::service::memory_io::MemoryIOImpl;
+use train_booking::service::seat_allocator::{FillFrontToBack, SeatAllocator};
+use train_booking::service::sqlite_io::SqliteIOImpl;
+
+fn user(id: &str) -> User {
+    User { user_id: id.to_string(), name: format!("Name {}", id), aadhar_card: AadharNumber::parse("234123412346").unwrap() }
+}
+
+fn seat(coach: &str, seat: u32) -> SeatNumber {
+    SeatNumber { coach: coach.to_string(), seat }
+}
+
+fn vehicle(id: &str, seats: SeatLayout) -> Vehicle {
+    Vehicle {
+        vehicle_id: id.to_string(),
+        name: "Express".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats,
+    }
+}
+
+// Returns a service over a fresh in-memory store, plus a handle to inspect what it stored.
+fn vehicle_service(existing: Vec<Vehicle>) -> (BookingServiceImpl<Vehicle>, MemoryIOImpl<Vehicle>) {
+    let store = MemoryIOImpl::with_entities(existing);
+    (BookingServiceImpl::with_storage(Box::new(store.clone()), Box::new(MemoryIOImpl::new())), store)
+}
+
+fn request(entity_id: &str, passengers: Vec<User>) -> BookingRequest {
+    BookingRequest::new(entity_id.to_string(), "Express".to_string(), "A".to_string(), "B".to_string(), passengers)
+}
+
+fn book(service: &BookingServiceImpl<Vehicle>, entity_id: &str, user_id: &str) -> Result<Ticket, BookingError> {
+    service.book(request(entity_id, vec![user(user_id)]))
+}
+
+fn book_group(service: &BookingServiceImpl<Vehicle>, entity_id: &str, user_ids: &[&str]) -> Result<Ticket, BookingError> {
+    service.book(request(entity_id, user_ids.iter().map(|id| user(id)).collect()))
+}
+
+// Seats `user_ids` under one new PNR where a front-to-back booking would put them.
+fn occupy(seats: &mut SeatLayout, user_ids: &[&str]) {
+    let positions = FillFrontToBack.allocate(seats, user_ids.len(), None).unwrap();
+    seats.assign_at(&positions, user_ids, &Pnr::generate(|_| false)).unwrap();
+}
+
+#[test]
+fn first_booking_creates_the_vehicle_with_the_default_layout() {
+    let (service, store) = vehicle_service(Vec::new());
+
+    assert_eq!(book(&service, "v1", "u1").unwrap().seats[0].seat, seat("S1", 1));
+
+    let stored = store.read_from_file().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].vehicle_id, "v1");
+    assert_eq!(stored[0].seats.capacity(), SeatLayout::default().capacity());
+}
+
+#[test]
+fn later_bookings_reuse_the_stored_vehicle() {
+    let (service, store) = vehicle_service(Vec::new());
+
+    book(&service, "v1", "u1").unwrap();
+    assert_eq!(book(&service, "v1", "u2").unwrap().seats[0].seat, seat("S1", 2));
+
+    let stored = store.read_from_file().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].seats.available(), stored[0].seats.capacity() - 2);
+}
+
+#[test]
+fn full_vehicle_is_sold_out_and_left_unchanged() {
+    let mut seats = SeatLayout::new(SeatClass::Sleeper, 1, 1, 1);
+    occupy(&mut seats, &["u1"]);
+    let (service, store) = vehicle_service(vec![vehicle("v1", seats)]);
+
+    assert!(matches!(book(&service, "v1", "u2"), Err(BookingError::SoldOut { entity_id, .. }) if entity_id == "v1"));
+    assert!(store.read_from_file().unwrap()[0].seats.seats_of("u2").is_empty());
+}
+
+#[test]
+fn empty_ids_are_rejected() {
+    let (service, store) = vehicle_service(Vec::new());
+
+    assert!(matches!(book(&service, " ", "u1"), Err(BookingError::Validation(_))));
+    assert!(matches!(book(&service, "v1", ""), Err(BookingError::Validation(_))));
+    assert!(store.read_from_file().unwrap().is_empty());
+}
+
+#[test]
+fn cancel_releases_every_seat_of_the_user() {
+    let (service, store) = vehicle_service(Vec::new());
+    book(&service, "v1", "u1").unwrap();
+    book(&service, "v1", "u2").unwrap();
+    book(&service, "v1", "u1").unwrap();
+
+    let released = service.cancel_booking("v1".to_string(), "u1".to_string()).unwrap();
+
+    assert_eq!(released, vec![seat("S1", 1), seat("S1", 3)]);
+    let stored = store.read_from_file().unwrap();
+    assert!(stored[0].seats.seats_of("u1").is_empty());
+    assert_eq!(stored[0].seats.seats_of("u2").len(), 1);
+}
+
+#[test]
+fn cancel_reports_unknown_vehicle_and_passenger() {
+    let (service, _) = vehicle_service(Vec::new());
+    book(&service, "v1", "u1").unwrap();
+
+    assert!(matches!(
+        service.cancel_booking("v2".to_string(), "u1".to_string()),
+        Err(BookingError::NotFound { kind: "Vehicle", .. })
+    ));
+    assert!(matches!(
+        service.cancel_booking("v1".to_string(), "u9".to_string()),
+        Err(BookingError::NoSuchPassenger { .. })
+    ));
+}
+
+#[test]
+fn get_booking_returns_the_entity_seats_and_passenger() {
+    let (service, _) = vehicle_service(Vec::new());
+    book(&service, "v1", "u1").unwrap();
+    book(&service, "v1", "u2").unwrap();
+
+    let view = service.get_booking("v1".to_string(), "u2".to_string()).unwrap();
+
+    assert_eq!(view.entity.kind, "Vehicle");
+    assert_eq!(view.entity.id, "v1");
+    assert_eq!(view.seats.len(), 1);
+    assert_eq!(view.seats[0].passenger.user_id, "u2");
+    assert_eq!(view.seats[0].position.number, seat("S1", 2));
+    assert_eq!((view.seats[0].position.row, view.seats[0].position.column), (0, 1));
+}
+
+#[test]
+fn serialized_views_mask_the_aadhar_number() {
+    let (service, _) = vehicle_service(Vec::new());
+    let ticket = book(&service, "v1", "u1").unwrap();
+    let view = service.get_booking_by_pnr(ticket.pnr.to_string()).unwrap();
+
+    let json = serde_json::to_string(&view).unwrap();
+
+    assert!(!json.contains("234123412346"));
+    assert_eq!(serde_json::to_value(&view).unwrap()["seats"][0]["passenger"]["aadharCard"], "XXXX XXXX 2346");
+    assert!(view.revealed().to_string().contains("234123412346"));
+}
+
+#[test]
+fn get_booking_reports_missing_bookings() {
+    let (service, _) = vehicle_service(Vec::new());
+    book(&service, "v1", "u1").unwrap();
+
+    assert!(matches!(service.get_booking("v2".to_string(), "u1".to_string()), Err(BookingError::NotFound { .. })));
+    assert!(matches!(service.get_booking("v1".to_string(), "u2".to_string()), Err(BookingError::NoSuchPassenger { .. })));
+}
+
+#[test]
+fn trains_are_booked_through_the_same_logic() {
+    let store = MemoryIOImpl::<Train>::new();
+    let service = BookingServiceImpl::with_storage(Box::new(store.clone()), Box::new(MemoryIOImpl::new()));
+
+    let ticket = service.book(request("t1", vec![user("u1")])).unwrap();
+
+    assert_eq!(ticket.seats[0].seat, seat("S1", 1));
+    assert_eq!(service.get_booking_by_pnr(ticket.pnr.to_string()).unwrap().entity.kind, "Train");
+    assert_eq!(service.get_booking("t1".to_string(), "u1".to_string()).unwrap().entity.kind, "Train");
+    assert_eq!(store.read_from_file().unwrap()[0].train_id, "t1");
+}
+
+#[test]
+fn passengers_are_registered_once_and_seats_refer_to_them() {
+    let users = MemoryIOImpl::<User>::new();
+    let trains = BookingServiceImpl::with_storage(Box::new(MemoryIOImpl::<Train>::new()), Box::new(users.clone()));
+    let vehicles = BookingServiceImpl::with_storage(Box::new(MemoryIOImpl::<Vehicle>::new()), Box::new(users.clone()));
+
+    trains.book(request("t1", vec![user("u1")])).unwrap();
+    book(&vehicles, "v1", "u1").unwrap();
+    book(&vehicles, "v2", "u1").unwrap();
+    assert_eq!(users.read_from_file().unwrap().len(), 1);
+
+    vehicles.users().rename("u1", "Renamed".to_string()).unwrap();
+    assert_eq!(vehicles.get_booking("v2".to_string(), "u1".to_string()).unwrap().seats[0].passenger.name, "Renamed");
+    assert_eq!(trains.get_booking("t1".to_string(), "u1".to_string()).unwrap().seats[0].passenger.name, "Renamed");
+}
+
+#[test]
+fn same_user_id_with_another_aadhar_number_is_refused() {
+    let (service, store) = vehicle_service(Vec::new());
+    book(&service, "v1", "u1").unwrap();
+    let mut impostor = user("u1");
+    impostor.aadhar_card = AadharNumber::parse("498765432102").unwrap();
+
+    let result = service.book(request("v1", vec![impostor]));
+
+    assert!(matches!(result, Err(BookingError::UserConflict { user_id }) if user_id == "u1"));
+    assert_eq!(store.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+    assert_eq!(service.users().get("u1").unwrap().reveal_aadhar(), "234123412346");
+}
+
+#[test]
+fn invalid_aadhar_numbers_are_neither_booked_nor_registered() {
+    let (service, store) = vehicle_service(Vec::new());
+    let mut passenger = user("u1");
+    // Deserializing is the only way to get an unchecked number outside the crate.
+    passenger.aadhar_card = serde_json::from_str(r#""1234""#).unwrap();
+
+    assert!(matches!(service.book(request("v1", vec![passenger.clone()])), Err(BookingError::Validation(_))));
+    assert!(matches!(service.users().register(&passenger), Err(BookingError::Validation(_))));
+    assert!(store.read_from_file().unwrap().is_empty());
+    assert!(service.users().all().unwrap().is_empty());
+}
+
+#[test]
+fn every_booking_gets_its_own_pnr() {
+    let (service, store) = vehicle_service(Vec::new());
+    let first = book(&service, "v1", "u1").unwrap();
+    let second = book(&service, "v1", "u1").unwrap();
+
+    assert_ne!(first.pnr, second.pnr);
+    assert_eq!(Pnr::parse(first.pnr.as_str()).unwrap(), first.pnr);
+    let stored = store.read_from_file().unwrap();
+    assert!(stored[0].seats.holds_pnr(&first.pnr) && stored[0].seats.holds_pnr(&second.pnr));
+}
+
+#[test]
+fn bookings_are_looked_up_and_cancelled_by_pnr() {
+    let (service, store) = vehicle_service(Vec::new());
+    let first = book(&service, "v1", "u1").unwrap();
+    let second = book(&service, "v1", "u1").unwrap();
+
+    let view = service.get_booking_by_pnr(second.pnr.as_str().to_lowercase()).unwrap();
+    assert_eq!(view.entity.id, "v1");
+    assert_eq!(view.seats.len(), 1);
+    assert_eq!(view.seats[0].position.number, second.seats[0].seat);
+    assert_eq!(view.seats[0].pnr.as_ref(), Some(&second.pnr));
+
+    assert_eq!(service.cancel_by_pnr(first.pnr.to_string()).unwrap(), vec![first.seats[0].seat.clone()]);
+    assert_eq!(store.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+    assert!(matches!(service.cancel_by_pnr(first.pnr.to_string()), Err(BookingError::UnknownPnr { .. })));
+    assert!(matches!(service.get_booking_by_pnr(first.pnr.to_string()), Err(BookingError::UnknownPnr { .. })));
+}
+
+#[test]
+fn mistyped_pnrs_are_rejected() {
+    let (service, _) = vehicle_service(Vec::new());
+    let ticket = book(&service, "v1", "u1").unwrap();
+    let mut typo: Vec<char> = ticket.pnr.as_str().chars().collect();
+    typo[0] = if typo[0] == '7' { '8' } else { '7' };
+
+    let result = service.cancel_by_pnr(typo.into_iter().collect());
+
+    assert!(matches!(result, Err(BookingError::Validation(_))));
+    assert!(service.get_booking_by_pnr(ticket.pnr.to_string()).is_ok());
+}
+
+#[test]
+fn group_bookings_seat_every_passenger_under_one_pnr() {
+    let (service, _) = vehicle_service(Vec::new());
+
+    let ticket = book_group(&service, "v1", &["u1", "u2", "u3"]).unwrap();
+
+    let seats: Vec<(&str, SeatNumber)> = ticket.seats.iter().map(|s| (s.user_id.as_str(), s.seat.clone())).collect();
+    assert_eq!(seats, vec![("u1", seat("S1", 1)), ("u2", seat("S1", 2)), ("u3", seat("S1", 3))]);
+    let view = service.get_booking_by_pnr(ticket.pnr.to_string()).unwrap();
+    let passengers: Vec<&str> = view.seats.iter().map(|s| s.passenger.user_id.as_str()).collect();
+    assert_eq!(passengers, vec!["u1", "u2", "u3"]);
+    assert_eq!(service.users().all().unwrap().len(), 3);
+}
+
+#[test]
+fn group_bookings_are_all_or_nothing() {
+    let mut seats = SeatLayout::new(SeatClass::Sleeper, 1, 1, 3);
+    occupy(&mut seats, &["u0"]);
+    let (service, store) = vehicle_service(vec![vehicle("v1", seats)]);
+
+    let user_ids = || service.users().all().unwrap().into_iter().map(|u| u.user_id).collect::<Vec<_>>();
+    let users_before = user_ids();
+
+    let result = book_group(&service, "v1", &["u1", "u2", "u3"]);
+
+    assert!(matches!(result, Err(BookingError::NotEnoughSeats { requested: 3, available: 2, .. })));
+    assert_eq!(store.read_from_file().unwrap()[0].seats.available(), 2);
+    // Nobody of a refused booking is registered.
+    assert_eq!(user_ids(), users_before);
+    assert_eq!(book_group(&service, "v1", &["u1", "u2"]).unwrap().seats.len(), 2);
+}
+
+#[test]
+fn invalid_group_requests_are_rejected() {
+    let (service, store) = vehicle_service(Vec::new());
+    let too_many: Vec<String> = (0..=MAX_PASSENGERS).map(|i| format!("u{}", i)).collect();
+    let too_many: Vec<&str> = too_many.iter().map(String::as_str).collect();
+
+    assert!(matches!(book_group(&service, "v1", &[]), Err(BookingError::Validation(_))));
+    assert!(matches!(book_group(&service, "v1", &["u1", "u1"]), Err(BookingError::Validation(_))));
+    assert!(matches!(book_group(&service, "v1", &too_many), Err(BookingError::Validation(_))));
+    assert!(store.read_from_file().unwrap().is_empty());
+}
+
+#[test]
+fn a_conflicting_passenger_registers_nobody() {
+    let (service, _) = vehicle_service(Vec::new());
+    book(&service, "v1", "u2").unwrap();
+    let mut impostor = user("u2");
+    impostor.aadhar_card = AadharNumber::parse("498765432102").unwrap();
+
+    let result = service.book(request("v1", vec![user("u1"), impostor]));
+
+    assert!(matches!(result, Err(BookingError::UserConflict { user_id }) if user_id == "u2"));
+    assert!(service.users().get("u1").is_err());
+}
+
+#[test]
+fn groups_are_kept_together_unless_another_allocator_is_set() {
+    // Seat S1-2 is taken, so the first three free seats are not adjacent.
+    let mut seats = SeatLayout::new(SeatClass::Sleeper, 1, 2, 4);
+    seats.coaches[0].rows[0][1].passenger = Some("u0".to_string());
+    let numbers = |ticket: Ticket| ticket.seats.into_iter().map(|s| s.seat.seat).collect::<Vec<_>>();
+
+    let (service, _) = vehicle_service(vec![vehicle("v1", seats.clone())]);
+    assert_eq!(numbers(book_group(&service, "v1", &["u1", "u2", "u3"]).unwrap()), vec![5, 6, 7]);
+
+    let (service, _) = vehicle_service(vec![vehicle("v1", seats)]);
+    let service = service.with_allocator(Box::new(FillFrontToBack));
+    assert_eq!(numbers(book_group(&service, "v1", &["u1", "u2", "u3"]).unwrap()), vec![1, 3, 4]);
+}
+
+#[test]
+fn seat_preferences_are_honoured_and_shortfalls_reported() {
+    // Rows of four with window seats at both ends; C1-1 is taken.
+    let mut seats = SeatLayout::new(SeatClass::ChairCar, 1, 2, 4);
+    seats.coaches[0].rows[0][0].passenger = Some("u0".to_string());
+    let (service, _) = vehicle_service(vec![vehicle("v1", seats)]);
+
+    let ticket = service.book(request("v1", vec![user("u1")]).with_preference(SeatKind::Window)).unwrap();
+    assert_eq!(ticket.seats[0].seat, seat("C1", 4));
+    assert!(ticket.unmet_preferences().is_empty());
+    assert_eq!(ticket.to_string(), format!("PNR {}: C1-4 (u1, window seat)", ticket.pnr));
+
+    // Two window seats are left, in row 2, but three passengers ask for them: they sit together.
+    let passengers = vec![user("u2"), user("u3"), user("u4")];
+    let ticket = service.book(request("v1", passengers).with_preference(SeatKind::Window)).unwrap();
+    let unmet: Vec<&str> = ticket.unmet_preferences().iter().map(|s| s.user_id.as_str()).collect();
+    assert_eq!(unmet, vec!["u3", "u4"]);
+    assert!(ticket.to_string().ends_with(
+        "C1-5 (u2, window seat), C1-6 (u3, aisle seat), C1-7 (u4, aisle seat); no window seat left for u3, u4"
+    ));
+}
+
+#[test]
+fn bookings_can_ask_for_a_class() {
+    let (service, _) = vehicle_service(Vec::new());
+
+    let ticket = service.book(request("v1", vec![user("u1"), user("u2")]).with_class(SeatClass::ThirdAc)).unwrap();
+
+    let seats: Vec<String> = ticket.seats.iter().map(|s| s.seat.to_string()).collect();
+    assert_eq!(seats, vec!["B1-1", "B1-2"]);
+    assert!(ticket.to_string().starts_with(&format!("PNR {} 3A: B1-1 (u1, lower berth)", ticket.pnr)));
+    // Without a class, bookings start at the front of the train.
+    assert_eq!(book(&service, "v1", "u3").unwrap().seats[0].seat, seat("S1", 1));
+}
+
+#[test]
+fn availability_is_reported_per_class() {
+    let (service, _) = vehicle_service(Vec::new());
+    service.book(request("v1", vec![user("u1")]).with_class(SeatClass::FirstAc)).unwrap();
+
+    let classes = service.availability("v1".to_string()).unwrap();
+
+    let codes: Vec<&str> = classes.iter().map(|c| c.class.code()).collect();
+    assert_eq!(codes, vec!["SL", "3A", "2A", "1A", "CC"]);
+    assert_eq!(classes[1].coaches, vec!["B1", "B2"]);
+    assert_eq!(classes[3].to_string(), "AC First Class (1A), coaches H1: 23 of 24 seats free");
+    assert_eq!(classes.iter().map(|c| c.capacity).sum::<usize>(), SeatLayout::default().capacity());
+    assert!(matches!(service.availability("v2".to_string()), Err(BookingError::NotFound { .. })));
+}
+
+#[test]
+fn a_full_or_missing_class_is_refused() {
+    let mut seats = SeatLayout::new(SeatClass::Sleeper, 1, 1, 2);
+    seats.coaches.extend(SeatLayout::new(SeatClass::ChairCar, 1, 1, 1).coaches);
+    occupy(&mut seats, &["u0"]);
+    let (service, store) = vehicle_service(vec![vehicle("v1", seats)]);
+    let book_in = |class, user_ids: &[&str]| {
+        service.book(request("v1", user_ids.iter().map(|id| user(id)).collect()).with_class(class))
+    };
+
+    let result = book_in(SeatClass::Sleeper, &["u1", "u2"]);
+    assert!(matches!(result, Err(BookingError::NotEnoughSeats { class: Some(SeatClass::Sleeper), available: 1, .. })));
+    book_in(SeatClass::Sleeper, &["u1"]).unwrap();
+    assert!(matches!(book_in(SeatClass::Sleeper, &["u2"]), Err(BookingError::SoldOut { class: Some(SeatClass::Sleeper), .. })));
+    assert!(matches!(book_in(SeatClass::FirstAc, &["u2"]), Err(BookingError::NoSuchClass { class: SeatClass::FirstAc, .. })));
+    assert!(service.users().get("u2").is_err());
+    assert_eq!(book_in(SeatClass::ChairCar, &["u2"]).unwrap().seats[0].seat, seat("C1", 1));
+    assert_eq!(store.read_from_file().unwrap()[0].seats.available(), 0);
+}
+
+#[test]
+fn passengers_can_be_cancelled_from_a_booking_individually() {
+    let (service, store) = vehicle_service(Vec::new());
+    let ticket = book_group(&service, "v1", &["u1", "u2", "u3"]).unwrap();
+    let pnr = ticket.pnr.to_string();
+
+    let released = service.cancel_passengers(pnr.clone(), vec!["u2".to_string()]).unwrap();
+
+    assert_eq!(released, vec![seat("S1", 2)]);
+    let view = service.get_booking_by_pnr(pnr.clone()).unwrap();
+    let passengers: Vec<&str> = view.seats.iter().map(|s| s.passenger.user_id.as_str()).collect();
+    assert_eq!(passengers, vec!["u1", "u3"]);
+
+    // Naming a passenger who isn't on the booking cancels nobody.
+    let result = service.cancel_passengers(pnr.clone(), vec!["u1".to_string(), "u2".to_string()]);
+    assert!(matches!(result, Err(BookingError::NotOnPnr { user_id, .. }) if user_id == "u2"));
+    assert_eq!(store.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+}
+
+#[test]
+fn memory_store_saves_by_id_and_deletes() {
+    let store = MemoryIOImpl::new();
+    store.save_to_file(&vehicle("v1", SeatLayout::default())).unwrap();
+    let mut renamed = vehicle("v1", SeatLayout::default());
+    renamed.name = "Renamed".to_string();
+    store.save_to_file(&renamed).unwrap();
+
+    let stored = store.read_from_file().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].name, "Renamed");
+    assert!(store.delete_from_file("v1").unwrap());
+    assert!(!store.delete_from_file("v1").unwrap());
+}
+
+// Books, looks up, refuses and cancels bookings of a two-seat vehicle through `service`, which keeps
+// its vehicles in `vehicles` and its passengers in a registry on the same backend.
+fn books_on_a_persistent_backend(service: BookingServiceImpl<Vehicle>, vehicles: &dyn FileIO<Item = Vehicle>) {
+    vehicles.save_to_file(&vehicle("v1", SeatLayout::new(SeatClass::ChairCar, 1, 1, 2))).unwrap();
+
+    // New passengers are registered before their seats refer to them.
+    let ticket = book(&service, "v1", "u1").unwrap();
+    assert_eq!(service.get_booking("v1".to_string(), "u1".to_string()).unwrap().seats[0].passenger.user_id, "u1");
+
+    // A refused booking reports its own error and registers none of its new passengers, while
+    // keeping the ones registered before.
+    let result = book_group(&service, "v1", &["u1", "u2"]);
+    assert!(matches!(result, Err(BookingError::NotEnoughSeats { requested: 2, available: 1, .. })));
+    assert!(service.users().get("u2").is_err());
+    assert!(service.users().get("u1").is_ok());
+
+    book(&service, "v1", "u2").unwrap();
+    assert!(matches!(book(&service, "v1", "u3"), Err(BookingError::SoldOut { .. })));
+    assert!(service.users().get("u3").is_err());
+
+    service.cancel_by_pnr(ticket.pnr.to_string()).unwrap();
+    assert_eq!(book(&service, "v1", "u3").unwrap().seats[0].seat, seat("C1", 1));
+    assert_eq!(vehicles.read_from_file().unwrap()[0].seats.available(), 0);
+}
+
+// Returns a service keeping its vehicles and its passengers in the given stores.
+fn persistent_service(vehicles: impl FileIO<Item = Vehicle> + 'static, users: impl FileIO<Item = User> + 'static) -> BookingServiceImpl<Vehicle> {
+    BookingServiceImpl::with_storage(Box::new(vehicles), Box::new(users))
+}
+
+#[test]
+fn bookings_are_kept_in_sqlite() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let service = persistent_service(SqliteIOImpl::<Vehicle>::with_path(&path), SqliteIOImpl::<User>::with_path(&path));
+
+    books_on_a_persistent_backend(service, &SqliteIOImpl::<Vehicle>::with_path(&path));
+}
+
+#[test]
+fn bookings_are_kept_in_db_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let service = persistent_service(FileIOImpl::<Vehicle>::with_path(&path), FileIOImpl::<User>::with_path(&path));
+
+    books_on_a_persistent_backend(service, &FileIOImpl::<Vehicle>::with_path(&path));
+}
+
+#[test]
+fn bookings_are_kept_in_the_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let service = persistent_service(JournalIOImpl::<Vehicle>::with_path(&path), JournalIOImpl::<User>::with_path(&path));
+
+    books_on_a_persistent_backend(service, &JournalIOImpl::<Vehicle>::with_path(&path));
+}
@@ -0,0 +1,128 @@
+// Tests for StorageConfig: parsing the config file, resolving its relative paths, and the
+// TRAIN_BOOKING_* environment variables taking precedence over it.
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+use train_booking::service::config::{Backend, StorageConfig, BACKEND_ENV, CONFIG_PATH_ENV, DB_PATH_ENV, KEY_FILE_ENV};
+
+#[test]
+fn the_config_file_is_parsed_on_top_of_the_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    fs::write(
+        &path,
+        "# storage settings\n\
+         backend = SQLite   # names are case-insensitive\n\
+         \n\
+         db_path = /var/lib/train_booking/db.sqlite3\n\
+         lock_timeout_ms = 250\n",
+    )
+    .unwrap();
+
+    let config = StorageConfig::from_file(&path).unwrap();
+
+    assert_eq!(config.backend, Backend::Sqlite);
+    assert_eq!(config.database_path(), PathBuf::from("/var/lib/train_booking/db.sqlite3"));
+    assert_eq!(config.lock_timeout, Duration::from_millis(250));
+    assert_eq!(config.backups_path(), PathBuf::from("/var/lib/train_booking/backups"));
+    assert_eq!(config.key_file, None);
+}
+
+#[test]
+fn relative_paths_are_resolved_against_the_config_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    fs::write(&path, "backend = journal\ndb_path = data/db.journal\nbackup_dir = ../backups\nkey_file = aadhar.key\n").unwrap();
+
+    let config = StorageConfig::from_file(&path).unwrap();
+
+    assert_eq!(config.database_path(), dir.path().join("data/db.journal"));
+    assert_eq!(config.backups_path(), dir.path().join("../backups"));
+    assert_eq!(config.key_file, Some(dir.path().join("aadhar.key")));
+}
+
+#[test]
+fn without_a_db_path_each_backend_uses_its_default_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    for (backend, file) in [("json", "db.json"), ("sqlite", "db.sqlite3"), ("journal", "db.journal")] {
+        fs::write(&path, format!("backend = {}\n", backend)).unwrap();
+        let config = StorageConfig::from_file(&path).unwrap();
+        assert_eq!(config.database_path(), PathBuf::from(file));
+        assert_eq!(config.backups_path(), PathBuf::from("backups"));
+    }
+}
+
+#[test]
+fn invalid_lines_are_reported_with_their_line_number() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    for (contents, message) in [
+        ("backend = json\nnot a setting\n", ":2: expected `key = value`"),
+        ("backend = postgres\n", ":1: backend must be"),
+        ("lock_timeout_ms = soon\n", ":1: lock_timeout_ms must be a number"),
+        ("\ncolour = blue\n", ":2: unknown setting `colour`"),
+    ] {
+        fs::write(&path, contents).unwrap();
+        let err = StorageConfig::from_file(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+}
+
+// The environment is shared by every test of this file, so all of load's cases run in this one test.
+#[test]
+fn environment_variables_take_precedence_over_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    fs::write(&path, "backend = sqlite\ndb_path = db.sqlite3\nkey_file = file.key\nlock_timeout_ms = 50\n").unwrap();
+    for name in [BACKEND_ENV, DB_PATH_ENV, KEY_FILE_ENV] {
+        env::remove_var(name);
+    }
+    env::set_var(CONFIG_PATH_ENV, &path);
+
+    // The file alone.
+    let config = StorageConfig::load().unwrap();
+    assert_eq!(config.backend, Backend::Sqlite);
+    assert_eq!(config.database_path(), dir.path().join("db.sqlite3"));
+    assert_eq!(config.key_file, Some(dir.path().join("file.key")));
+
+    // Each variable overrides its setting; the others still come from the file.
+    env::set_var(BACKEND_ENV, "journal");
+    env::set_var(DB_PATH_ENV, "/srv/db.journal");
+    env::set_var(KEY_FILE_ENV, "/etc/env.key");
+    let config = StorageConfig::load().unwrap();
+    assert_eq!(config.backend, Backend::Journal);
+    assert_eq!(config.database_path(), PathBuf::from("/srv/db.journal"));
+    assert_eq!(config.key_file, Some(PathBuf::from("/etc/env.key")));
+    assert_eq!(config.lock_timeout, Duration::from_millis(50));
+
+    // Empty variables count as unset.
+    for name in [BACKEND_ENV, DB_PATH_ENV, KEY_FILE_ENV] {
+        env::set_var(name, "");
+    }
+    assert_eq!(StorageConfig::load().unwrap().backend, Backend::Sqlite);
+
+    // An unknown backend in the environment is refused rather than ignored.
+    env::set_var(BACKEND_ENV, "postgres");
+    assert_eq!(StorageConfig::load().unwrap_err().kind(), ErrorKind::InvalidInput);
+    env::remove_var(BACKEND_ENV);
+
+    // A config file named by TRAIN_BOOKING_CONFIG must exist.
+    env::set_var(CONFIG_PATH_ENV, dir.path().join("missing"));
+    assert_eq!(StorageConfig::load().unwrap_err().kind(), ErrorKind::NotFound);
+
+    // Otherwise the file in the default location is optional.
+    env::remove_var(CONFIG_PATH_ENV);
+    env::set_var("XDG_CONFIG_HOME", dir.path());
+    let config = StorageConfig::load().unwrap();
+    assert_eq!(config.backend, Backend::Json);
+    assert_eq!(config.database_path(), PathBuf::from("db.json"));
+    fs::create_dir(dir.path().join("train_booking")).unwrap();
+    fs::write(dir.path().join("train_booking").join("config"), "backend = sqlite\n").unwrap();
+    assert_eq!(StorageConfig::load().unwrap().backend, Backend::Sqlite);
+    env::set_var(DB_PATH_ENV, "env.sqlite3");
+    assert_eq!(StorageConfig::load().unwrap().database_path(), PathBuf::from("env.sqlite3"));
+}
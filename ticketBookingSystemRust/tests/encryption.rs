@@ -0,0 +1,99 @@
+// Tests for encrypting Aadhar numbers at rest and rotating the key.
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::user::User;
+use train_booking::service::encryption::{self, AadharCipher, EncryptedIO};
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+
+const ASHA: &str = "234123412346";
+const RAVI: &str = "498765432102";
+
+fn user(id: &str, aadhar: &str) -> User {
+    User { user_id: id.to_string(), name: format!("Name {}", id), aadhar_card: AadharNumber::parse(aadhar).unwrap() }
+}
+
+fn cipher(byte: u8) -> AadharCipher {
+    AadharCipher::new(&[byte; encryption::KEY_LEN])
+}
+
+fn encrypted(path: &Path, cipher: AadharCipher) -> EncryptedIO<User> {
+    EncryptedIO::new(Box::new(FileIOImpl::<User>::with_path(path)), cipher)
+}
+
+#[test]
+fn the_file_holds_no_plaintext_aadhar_numbers() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let storage = encrypted(&path, cipher(1));
+    storage.save_to_file(&user("u1", ASHA)).unwrap();
+    storage.save_to_file(&user("u2", RAVI)).unwrap();
+
+    let contents = fs::read_to_string(&path).unwrap();
+
+    assert!(!contents.contains(ASHA) && !contents.contains(RAVI));
+    let stored = FileIOImpl::<User>::with_path(&path).read_from_file().unwrap();
+    assert!(stored.iter().all(|u| u.aadhar_card.is_encrypted()));
+}
+
+#[test]
+fn reads_decrypt_the_stored_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    encrypted(&path, cipher(1)).save_to_file(&user("u1", ASHA)).unwrap();
+
+    let users = encrypted(&path, cipher(1)).read_from_file().unwrap();
+
+    assert_eq!(users[0].reveal_aadhar(), ASHA);
+}
+
+#[test]
+fn unchanged_values_keep_their_ciphertext() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let storage = encrypted(&path, cipher(1));
+    storage.save_to_file(&user("u1", ASHA)).unwrap();
+    let before = fs::read_to_string(&path).unwrap();
+
+    storage.update_in_file(&mut |_| Ok(())).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+}
+
+#[test]
+fn a_wrong_key_is_an_error_not_garbage() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    encrypted(&path, cipher(1)).save_to_file(&user("u1", ASHA)).unwrap();
+
+    let error = encrypted(&path, cipher(2)).read_from_file().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+    // A value claiming the right key but altered on disk fails authentication.
+    let mut tampered = FileIOImpl::<User>::with_path(&path).read_from_file().unwrap()[0].reveal_aadhar().to_string();
+    let at = tampered.len() - 10;
+    let replacement = if &tampered[at..at + 1] == "A" { "B" } else { "A" };
+    tampered.replace_range(at..at + 1, replacement);
+    assert_eq!(cipher(1).decrypt(&tampered).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn rotating_twice_gives_the_same_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let plain = FileIOImpl::<User>::with_path(&path);
+    plain.save_to_file(&user("u1", ASHA)).unwrap();
+    plain.save_to_file(&user("u2", RAVI)).unwrap();
+
+    // Plaintext values are encrypted under the first key, then moved to the second.
+    assert_eq!(encryption::rotate_key(&plain, None, &cipher(1)).unwrap(), 2);
+    assert_eq!(encryption::rotate_key(&plain, Some(&cipher(1)), &cipher(2)).unwrap(), 2);
+    let rotated = fs::read_to_string(&path).unwrap();
+
+    assert_eq!(encryption::rotate_key(&plain, Some(&cipher(1)), &cipher(2)).unwrap(), 0);
+    assert_eq!(fs::read_to_string(&path).unwrap(), rotated);
+    let users = encrypted(&path, cipher(2)).read_from_file().unwrap();
+    assert_eq!(users[0].reveal_aadhar(), ASHA);
+    assert_eq!(users[1].reveal_aadhar(), RAVI);
+}
@@ -0,0 +1,119 @@
+// Tests for the advisory lock on "<path>.lock" that serialises writers of db.json, held here from a
+// second handle the way another process would hold it.
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::SeatLayout;
+use train_booking::entities::user::User;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::booking_error::BookingError;
+use train_booking::service::booking_request::BookingRequest;
+use train_booking::service::booking_service::{BookingService, BookingServiceImpl};
+use train_booking::service::file_io::{lock_database, sibling_path, FileIO, FileIOImpl};
+
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+fn user(id: &str) -> User {
+    User { user_id: id.to_string(), name: format!("Name {}", id), aadhar_card: AadharNumber::parse("234123412346").unwrap() }
+}
+
+fn request(passengers: Vec<User>) -> BookingRequest {
+    BookingRequest::new("v1".to_string(), "Express".to_string(), "A".to_string(), "B".to_string(), passengers)
+}
+
+fn vehicle() -> Vehicle {
+    Vehicle {
+        vehicle_id: "v1".to_string(),
+        name: "Express".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats: SeatLayout::new(SeatClass::ChairCar, 1, 1, 2),
+    }
+}
+
+// Returns a booking service over db.json at `path` that gives up on the lock after TIMEOUT.
+fn service(path: &Path) -> BookingServiceImpl<Vehicle> {
+    BookingServiceImpl::with_storage(
+        Box::new(FileIOImpl::<Vehicle>::with_path(path).with_lock_timeout(TIMEOUT)),
+        Box::new(FileIOImpl::<User>::with_path(path).with_lock_timeout(TIMEOUT)),
+    )
+}
+
+// Opens "<path>.lock" through a handle of its own, as another process would.
+fn lock_file(path: &Path) -> File {
+    OpenOptions::new().read(true).write(true).create(true).truncate(false).open(sibling_path(path, ".lock")).unwrap()
+}
+
+// Returns true if the lock can be taken exclusively right away, i.e. nobody holds it any more.
+fn is_free(path: &Path) -> bool {
+    let file = lock_file(path);
+    let free = file.try_lock().is_ok();
+    if free {
+        file.unlock().unwrap();
+    }
+    free
+}
+
+#[test]
+fn a_held_exclusive_lock_times_out_reads_and_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let storage = FileIOImpl::<Vehicle>::with_path(&path).with_lock_timeout(TIMEOUT);
+    storage.save_to_file(&vehicle()).unwrap();
+    let other = lock_file(&path);
+    other.lock().unwrap();
+
+    assert!(matches!(service(&path).book(request(vec![user("u1")])), Err(BookingError::LockTimeout(_))));
+    assert_eq!(storage.read_from_file().unwrap_err().kind(), ErrorKind::TimedOut);
+    assert_eq!(lock_database(&path, false, TIMEOUT).unwrap_err().kind(), ErrorKind::TimedOut);
+
+    other.unlock().unwrap();
+    assert_eq!(service(&path).book(request(vec![user("u1")])).unwrap().seats.len(), 1);
+    assert!(is_free(&path));
+}
+
+#[test]
+fn a_held_shared_lock_lets_reads_through_and_times_out_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let storage = FileIOImpl::<Vehicle>::with_path(&path).with_lock_timeout(TIMEOUT);
+    storage.save_to_file(&vehicle()).unwrap();
+    let other = lock_file(&path);
+    other.lock_shared().unwrap();
+
+    assert_eq!(storage.read_from_file().unwrap().len(), 1);
+    assert!(matches!(service(&path).book(request(vec![user("u1")])), Err(BookingError::LockTimeout(_))));
+    assert!(service(&path).users().all().unwrap().is_empty());
+
+    drop(other);
+    assert!(service(&path).book(request(vec![user("u1")])).is_ok());
+}
+
+#[test]
+fn the_lock_is_released_after_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let storage = FileIOImpl::<Vehicle>::with_path(&path).with_lock_timeout(TIMEOUT);
+    storage.save_to_file(&vehicle()).unwrap();
+
+    let failed = storage.update_in_file(&mut |_| Err(io::Error::other("refused")));
+    assert_eq!(failed.unwrap_err().to_string(), "refused");
+    assert!(is_free(&path));
+
+    // A booking refused for want of seats releases the lock as well.
+    let refused = service(&path).book(request(vec![user("u1"), user("u2"), user("u3")]));
+    assert!(matches!(refused, Err(BookingError::NotEnoughSeats { .. })));
+    assert!(is_free(&path));
+
+    // So does a timed-out attempt, once the other holder lets go.
+    let other = lock_file(&path);
+    other.lock().unwrap();
+    assert_eq!(storage.update_in_file(&mut |_| Ok(())).unwrap_err().kind(), ErrorKind::TimedOut);
+    drop(other);
+    assert!(is_free(&path));
+    assert!(storage.update_in_file(&mut |_| Ok(())).is_ok());
+}
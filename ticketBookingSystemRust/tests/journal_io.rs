@@ -0,0 +1,202 @@
+// Tests for the journal backend: replay, snapshots, appends after a snapshot, torn lines, compaction and restores.
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use train_booking::entities::pnr::Pnr;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::SeatLayout;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::file_io::FileIO;
+use train_booking::service::journal_io::JournalIOImpl;
+use train_booking::service::seat_allocator::{FillFrontToBack, SeatAllocator};
+
+fn vehicle(id: &str) -> Vehicle {
+    Vehicle {
+        vehicle_id: id.to_string(),
+        name: "Express".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats: SeatLayout::new(SeatClass::ChairCar, 1, 2, 4),
+    }
+}
+
+// Seats `user_ids` under one new PNR where a front-to-back booking would put them.
+fn occupy(seats: &mut SeatLayout, user_ids: &[&str]) {
+    let positions = FillFrontToBack.allocate(seats, user_ids.len(), None).unwrap();
+    seats.assign_at(&positions, user_ids, &Pnr::generate(|_| false)).unwrap();
+}
+
+// Seats `user_id` on the first free seat of vehicle `id`, appending one journal entry.
+fn book(journal: &JournalIOImpl<Vehicle>, id: &str, user_id: &str) {
+    journal
+        .update_in_file(&mut |vehicles| {
+            let vehicle = vehicles.iter_mut().find(|v| v.vehicle_id == id).unwrap();
+            occupy(&mut vehicle.seats, &[user_id]);
+            Ok(())
+        })
+        .unwrap();
+}
+
+// Returns the sequence numbers of the entries in the journal file, skipping its header.
+fn journal_seqs(path: &Path) -> Vec<u64> {
+    let contents = fs::read_to_string(path).unwrap();
+    contents.lines().skip(1).map(|line| serde_json::from_str::<Value>(line).unwrap()["seq"].as_u64().unwrap()).collect()
+}
+
+fn snapshot_seq(journal: &JournalIOImpl<Vehicle>) -> u64 {
+    let snapshot: Value = serde_json::from_str(&fs::read_to_string(journal.snapshot_path()).unwrap()).unwrap();
+    snapshot["seq"].as_u64().unwrap()
+}
+
+#[test]
+fn entries_are_replayed_by_a_new_reader() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+    book(&journal, "v1", "u2");
+
+    let vehicles = JournalIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap();
+
+    assert_eq!(vehicles.len(), 1);
+    assert_eq!(vehicles[0].seats.seats_of("u2").len(), 1);
+    assert_eq!(vehicles[0].seats.available(), 6);
+    assert_eq!(journal_seqs(&path), vec![1, 2, 3]);
+    assert!(!journal.snapshot_path().exists());
+}
+
+#[test]
+fn snapshots_are_taken_at_the_interval() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal = JournalIOImpl::<Vehicle>::with_path(dir.path().join("db.journal")).with_snapshot_interval(3);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+    assert!(!journal.snapshot_path().exists());
+
+    book(&journal, "v1", "u2");
+
+    assert_eq!(snapshot_seq(&journal), 3);
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.available(), 6);
+}
+
+#[test]
+fn appends_after_a_snapshot_keep_the_entries_it_covers() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path).with_snapshot_interval(3);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+    book(&journal, "v1", "u2");
+
+    book(&journal, "v1", "u3");
+
+    // The journal is the complete history, so entries 1-3 stay next to the snapshot taken at 3.
+    assert_eq!(journal_seqs(&path), vec![1, 2, 3, 4]);
+    assert_eq!(snapshot_seq(&journal), 3);
+    let vehicles = journal.read_from_file().unwrap();
+    assert_eq!(vehicles[0].seats.seats_of("u3").len(), 1);
+    assert_eq!(vehicles[0].seats.available(), 5);
+}
+
+#[test]
+fn a_torn_last_line_is_ignored_and_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+    // A crash cut the next append short.
+    let mut contents = fs::read_to_string(&path).unwrap();
+    contents.push_str("{\"seq\":3,\"at\":0,\"ev");
+    fs::write(&path, contents).unwrap();
+
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.available(), 7);
+
+    book(&journal, "v1", "u2");
+    assert_eq!(journal_seqs(&path), vec![1, 2, 3]);
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.available(), 6);
+}
+
+#[test]
+fn compaction_archives_the_journal_and_keeps_the_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+
+    let report = journal.compact().unwrap();
+
+    assert_eq!(report.entries, 2);
+    assert_eq!(report.seq, 2);
+    let archive = dir.path().join("db.journal.archive-2");
+    assert_eq!(report.archived_to, Some(archive.display().to_string()));
+    assert_eq!(journal_seqs(&archive), vec![1, 2]);
+    assert!(journal_seqs(&path).is_empty());
+    assert_eq!(snapshot_seq(&journal), 2);
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+
+    // Numbering continues after the snapshot.
+    book(&journal, "v1", "u2");
+    assert_eq!(journal_seqs(&path), vec![3]);
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.available(), 6);
+}
+
+#[test]
+fn a_restore_swaps_in_the_backup_and_moves_aside_newer_archives() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    book(&journal, "v1", "u1");
+    journal.compact().unwrap();
+    let backup = dir.path().join("backup");
+    fs::create_dir(&backup).unwrap();
+    journal.backup_to(&backup).unwrap();
+
+    book(&journal, "v1", "u2");
+    journal.compact().unwrap();
+    book(&journal, "v1", "u3");
+    journal.restore_from(&backup).unwrap();
+
+    let vehicles = journal.read_from_file().unwrap();
+    assert_eq!(vehicles[0].seats.seats_of("u1").len(), 1);
+    assert_eq!(vehicles[0].seats.available(), 7);
+    assert_eq!(snapshot_seq(&journal), 2);
+    assert!(dir.path().join("db.journal.archive-2").exists());
+    assert!(!dir.path().join("db.journal.archive-3").exists());
+    assert!(!dir.path().join("db.journal.restoring").exists());
+    let replaced: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("db.journal.replaced-"))
+        .collect();
+    assert_eq!(replaced.len(), 1);
+    let aside = replaced[0].path();
+    assert_eq!(journal_seqs(&aside.join("archive-3.jsonl")), vec![3]);
+    assert_eq!(journal_seqs(&aside.join("journal.jsonl")), vec![4]);
+}
+
+#[test]
+fn a_restore_that_cannot_be_staged_changes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+    let journal = JournalIOImpl::<Vehicle>::with_path(&path);
+    journal.save_to_file(&vehicle("v1")).unwrap();
+    let backup = dir.path().join("backup");
+    fs::create_dir(&backup).unwrap();
+    journal.backup_to(&backup).unwrap();
+    // An archive that can't be copied makes staging fail after the journal was copied.
+    fs::create_dir(backup.join("archive-9.jsonl")).unwrap();
+    book(&journal, "v1", "u1");
+    let before = fs::read(&path).unwrap();
+
+    assert!(journal.restore_from(&backup).is_err());
+
+    assert_eq!(fs::read(&path).unwrap(), before);
+    assert_eq!(journal.read_from_file().unwrap()[0].seats.seats_of("u1").len(), 1);
+    assert!(!dir.path().join("db.journal.restoring").exists());
+    assert!(!dir.path().join("db.journal.archive-9").exists());
+}
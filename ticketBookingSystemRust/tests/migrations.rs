@@ -0,0 +1,258 @@
+// Tests for the db.json schema migrations and the versioned envelope.
+use std::fs;
+use serde_json::{json, Value};
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::file_io::migrations::{
+    self, assign_coach_classes, assign_pnrs, assign_seat_kinds, extract_users, seat_grid_to_layout, split_collections, unwrap_envelope, RenamedUser, CURRENT_VERSION,
+};
+use train_booking::entities::pnr::Pnr;
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+
+// A record of the original flat layout, with seats stored as a grid of users.
+fn legacy_vehicle() -> Value {
+    json!({
+        "vehicleId": "123",
+        "name": "Express",
+        "source": "A",
+        "destination": "B",
+        "time": 1700000000,
+        "seats": [[{ "userId": "u1", "name": "Asha", "aadharCard": "1111" }],
+                  [{ "userId": "u2", "name": "Ravi", "aadharCard": "2222" }]]
+    })
+}
+
+#[test]
+fn detects_version_of_each_layout() {
+    assert_eq!(unwrap_envelope(json!([])).unwrap().0, 0);
+    assert_eq!(unwrap_envelope(json!({ "trains": [], "vehicles": [] })).unwrap().0, 1);
+    let (version, data) = unwrap_envelope(json!({ "schemaVersion": 2, "data": { "trains": [] } })).unwrap();
+    assert_eq!(version, 2);
+    assert_eq!(data, json!({ "trains": [] }));
+    assert!(unwrap_envelope(json!({ "schemaVersion": 2 })).is_err());
+    assert!(unwrap_envelope(json!("not a database")).is_err());
+}
+
+#[test]
+fn v0_splits_records_into_collections() {
+    let train = json!({ "trainId": "t1" });
+    let stray = json!({ "name": "no id" });
+    let data = split_collections(json!([legacy_vehicle(), train, stray])).unwrap();
+
+    assert_eq!(data["vehicles"], json!([legacy_vehicle()]));
+    assert_eq!(data["trains"], json!([train]));
+    assert_eq!(data["unclassified"], json!([stray]));
+}
+
+#[test]
+fn v1_converts_seat_grid_to_layout_keeping_passengers() {
+    let data = seat_grid_to_layout(json!({ "trains": [], "vehicles": [legacy_vehicle()] })).unwrap();
+
+    let coaches = data["vehicles"][0]["seats"]["coaches"].as_array().unwrap();
+    assert_eq!(coaches.len(), 3);
+    assert_eq!(coaches[0]["code"], "S1");
+    assert_eq!(coaches[0]["rows"].as_array().unwrap().len(), 12);
+    let first_row = &coaches[0]["rows"][0];
+    assert_eq!(first_row[0], json!({ "number": 1, "passenger": { "userId": "u1", "name": "Asha", "aadharCard": "1111" } }));
+    assert_eq!(first_row[1]["passenger"]["userId"], "u2");
+    assert_eq!(first_row[2]["passenger"], Value::Null);
+}
+
+#[test]
+fn v1_adds_coaches_for_oversized_grids() {
+    let user = json!({ "userId": "u", "name": "N", "aadharCard": "1" });
+    let grid = vec![vec![user; 250]];
+    let data = seat_grid_to_layout(json!({ "trains": [{ "trainId": "t", "seats": grid }] })).unwrap();
+
+    assert_eq!(data["trains"][0]["seats"]["coaches"].as_array().unwrap().len(), 4);
+}
+
+#[test]
+fn v2_moves_passengers_into_the_users_registry() {
+    let seat = |number: u32, passenger: Value| json!({ "number": number, "passenger": passenger });
+    let asha = json!({ "userId": "u1", "name": "Asha", "aadharCard": "1111" });
+    let other_u1 = json!({ "userId": "u1", "name": "Someone else", "aadharCard": "2222" });
+    let layout = |seats: Vec<Value>| json!({ "coaches": [{ "code": "S1", "rows": [seats] }] });
+    let data = json!({
+        "trains": [{ "trainId": "t1", "seats": layout(vec![seat(1, asha.clone()), seat(2, Value::Null)]) }],
+        "vehicles": [{ "vehicleId": "v1", "seats": layout(vec![seat(1, asha.clone()), seat(2, other_u1)]) }],
+    });
+
+    let mut renamed = Vec::new();
+    let data = extract_users(data, &mut renamed).unwrap();
+
+    let users = data["users"].as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0], asha);
+    assert_eq!(users[1]["userId"], "u1~2");
+    assert_eq!(users[1]["aadharCard"], "2222");
+    let train_row = &data["trains"][0]["seats"]["coaches"][0]["rows"][0];
+    assert_eq!(train_row[0]["passenger"], "u1");
+    assert_eq!(train_row[1]["passenger"], Value::Null);
+    let vehicle_row = &data["vehicles"][0]["seats"]["coaches"][0]["rows"][0];
+    assert_eq!(vehicle_row[0]["passenger"], "u1");
+    assert_eq!(vehicle_row[1]["passenger"], "u1~2");
+    assert_eq!(
+        renamed,
+        vec![RenamedUser {
+            user_id: "u1".to_string(),
+            renamed_to: "u1~2".to_string(),
+            name: "Someone else".to_string(),
+            seated_on: vec!["vehicles/v1".to_string()],
+        }]
+    );
+}
+
+#[test]
+fn v2_refuses_to_compare_encrypted_aadhar_numbers() {
+    let seat = |number: u32, aadhar: &str| json!({ "number": number, "passenger": { "userId": "u1", "name": "Asha", "aadharCard": aadhar } });
+    let layout = |seats: Vec<Value>| json!({ "coaches": [{ "code": "S1", "rows": [seats] }] });
+    let same = json!({ "vehicles": [{ "vehicleId": "v1", "seats": layout(vec![seat(1, "enc:v1:k:AAAA"), seat(2, "enc:v1:k:AAAA")]) }] });
+    let different = json!({ "vehicles": [{ "vehicleId": "v1", "seats": layout(vec![seat(1, "enc:v1:k:AAAA"), seat(2, "enc:v1:k:BBBB")]) }] });
+
+    assert_eq!(extract_users(same, &mut Vec::new()).unwrap()["users"].as_array().unwrap().len(), 1);
+    // Two ciphertexts may or may not hold the same number, so neither merging nor splitting is safe.
+    assert_eq!(extract_users(different, &mut Vec::new()).unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn v3_gives_each_existing_booking_one_stable_pnr() {
+    let seat = |number: u32, passenger: Option<&str>| json!({ "number": number, "passenger": passenger });
+    let layout = |seats: Vec<Value>| json!({ "coaches": [{ "code": "S1", "rows": [seats] }] });
+    let data = json!({
+        "trains": [{ "trainId": "t1", "seats": layout(vec![seat(1, Some("u1")), seat(2, Some("u2")), seat(3, Some("u1")), seat(4, None)]) }],
+        "vehicles": [{ "vehicleId": "v1", "seats": layout(vec![seat(1, Some("u1"))]) }],
+    });
+
+    let migrated = assign_pnrs(data.clone()).unwrap();
+
+    let row = &migrated["trains"][0]["seats"]["coaches"][0]["rows"][0];
+    let pnr = |seat: &Value| Pnr::parse(seat["pnr"].as_str().unwrap()).unwrap();
+    assert_eq!(pnr(&row[0]), pnr(&row[2]));
+    assert_ne!(pnr(&row[0]), pnr(&row[1]));
+    assert!(row[3].get("pnr").is_none());
+    assert_ne!(pnr(&migrated["vehicles"][0]["seats"]["coaches"][0]["rows"][0][0]), pnr(&row[0]));
+    assert_eq!(assign_pnrs(data).unwrap(), migrated);
+}
+
+#[test]
+fn v4_marks_seats_by_their_place_in_the_row() {
+    let row = |width: u32| (1..=width).map(|number| json!({ "number": number, "passenger": null })).collect::<Vec<_>>();
+    let data = json!({
+        "trains": [{ "trainId": "t1", "seats": { "coaches": [{ "code": "S1", "rows": [row(6), row(4)] }] } }],
+        "vehicles": [{ "vehicleId": "v1", "seats": { "coaches": [{ "code": "S1", "rows": [[{ "number": 1, "kind": "lowerBerth" }]] }] } }],
+    });
+
+    let migrated = assign_seat_kinds(data).unwrap();
+
+    let kinds = |row: &Value| row.as_array().unwrap().iter().map(|s| s["kind"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+    let rows = &migrated["trains"][0]["seats"]["coaches"][0]["rows"];
+    assert_eq!(kinds(&rows[0]), ["window", "middle", "aisle", "aisle", "middle", "window"]);
+    assert_eq!(kinds(&rows[1]), ["window", "aisle", "aisle", "window"]);
+    // Seats that already have a kind keep it.
+    assert_eq!(migrated["vehicles"][0]["seats"]["coaches"][0]["rows"][0][0]["kind"], "lowerBerth");
+}
+
+#[test]
+fn v5_classes_existing_coaches_as_chair_car() {
+    let coach = |code: &str, class: Option<&str>| json!({ "code": code, "class": class, "rows": [[{ "number": 1, "passenger": "u1" }]] });
+    let data = json!({
+        "trains": [{ "trainId": "t1", "seats": { "coaches": [coach("S1", None), coach("B1", Some("3A"))] } }],
+        "vehicles": [],
+    });
+
+    let migrated = assign_coach_classes(data).unwrap();
+
+    let coaches = &migrated["trains"][0]["seats"]["coaches"];
+    // Their rows of window, middle and aisle seats are seats, not berths.
+    assert_eq!(coaches[0]["class"], "CC");
+    assert_eq!(coaches[1]["class"], "3A");
+    assert_eq!(coaches[0]["rows"][0][0]["passenger"], "u1");
+}
+
+#[test]
+fn full_chain_upgrades_the_original_layout() {
+    let (data, report) = migrations::migrate(0, json!([legacy_vehicle()])).unwrap();
+
+    assert_eq!(report.from_version, 0);
+    assert_eq!(report.to_version, CURRENT_VERSION);
+    assert_eq!(report.applied.len(), migrations::MIGRATIONS.len());
+    assert!(data["vehicles"][0]["seats"]["coaches"].is_array());
+}
+
+#[test]
+fn current_version_needs_no_migration() {
+    let data = json!({ "trains": [], "vehicles": [] });
+    let (migrated, report) = migrations::migrate(CURRENT_VERSION, data.clone()).unwrap();
+
+    assert!(report.applied.is_empty());
+    assert_eq!(migrated, data);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    assert!(migrations::migrate(CURRENT_VERSION + 1, json!({})).is_err());
+}
+
+#[test]
+fn legacy_file_is_readable_and_dry_run_leaves_it_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let original = serde_json::to_string(&json!([legacy_vehicle()])).unwrap();
+    fs::write(&path, &original).unwrap();
+    let file = FileIOImpl::<Vehicle>::with_path(&path);
+
+    let vehicles = file.read_from_file().unwrap();
+    assert_eq!(vehicles[0].seats.seats_of("u2").len(), 1);
+
+    let report = file.migrate(true).unwrap();
+    assert_eq!(report.from_version, 0);
+    assert_eq!(report.applied.len(), 6);
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+}
+
+#[test]
+fn renamed_passengers_are_reported_with_and_without_dry_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let mut vehicle = legacy_vehicle();
+    vehicle["seats"][1][0]["userId"] = json!("u1");
+    let original = serde_json::to_string(&json!([vehicle])).unwrap();
+    fs::write(&path, &original).unwrap();
+    let file = FileIOImpl::<Vehicle>::with_path(&path);
+    let expected = vec![RenamedUser {
+        user_id: "u1".to_string(),
+        renamed_to: "u1~2".to_string(),
+        name: "Ravi".to_string(),
+        seated_on: vec!["vehicles/123".to_string()],
+    }];
+
+    assert_eq!(file.migrate(true).unwrap().renamed, expected);
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    assert_eq!(file.migrate(false).unwrap().renamed, expected);
+    assert!(file.migrate(false).unwrap().renamed.is_empty());
+}
+
+#[test]
+fn migrate_writes_the_versioned_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, serde_json::to_string(&json!([legacy_vehicle()])).unwrap()).unwrap();
+    let file = FileIOImpl::<Vehicle>::with_path(&path);
+
+    file.migrate(false).unwrap();
+
+    let stored: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(stored["schemaVersion"], CURRENT_VERSION);
+    assert_eq!(stored["data"]["vehicles"][0]["vehicleId"], "123");
+    assert!(file.migrate(false).unwrap().applied.is_empty());
+}
+
+#[test]
+fn newer_file_is_not_treated_as_corrupt() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, json!({ "schemaVersion": CURRENT_VERSION + 1, "data": {} }).to_string()).unwrap();
+
+    let err = FileIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
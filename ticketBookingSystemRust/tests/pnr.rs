@@ -0,0 +1,66 @@
+// Tests for PNR generation and check characters.
+use train_booking::entities::pnr::{Pnr, PnrError, PNR_LEN};
+
+// The characters PNRs are written with.
+const ALPHABET: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[test]
+fn generated_pnrs_parse_back() {
+    for _ in 0..100 {
+        let pnr = Pnr::generate(|_| false);
+        assert_eq!(pnr.as_str().len(), PNR_LEN);
+        assert_eq!(Pnr::parse(pnr.as_str()).unwrap(), pnr);
+    }
+}
+
+#[test]
+fn generate_skips_taken_pnrs() {
+    let taken = Pnr::generate(|_| false);
+    let mut draws = 0;
+    let pnr = Pnr::generate(|candidate| {
+        draws += 1;
+        draws == 1 || candidate == &taken
+    });
+    assert_ne!(pnr, taken);
+    assert!(draws >= 2);
+}
+
+#[test]
+fn parse_accepts_lower_case_separators_and_look_alikes() {
+    let pnr = Pnr::from_seed("seed");
+    let lower = pnr.as_str().to_lowercase();
+    assert_eq!(Pnr::parse(&lower).unwrap(), pnr);
+    let grouped = format!("{}-{} {}", &pnr.as_str()[..3], &pnr.as_str()[3..6], &pnr.as_str()[6..]);
+    assert_eq!(Pnr::parse(&grouped).unwrap(), pnr);
+    let look_alikes = pnr.as_str().replace('0', "O").replace('1', "I");
+    assert_eq!(Pnr::parse(&look_alikes).unwrap(), pnr);
+}
+
+#[test]
+fn every_single_character_typo_is_caught() {
+    let pnr = Pnr::from_seed("seed");
+    for position in 0..PNR_LEN {
+        for replacement in ALPHABET.chars() {
+            let mut typo: Vec<char> = pnr.as_str().chars().collect();
+            if typo[position] == replacement {
+                continue;
+            }
+            typo[position] = replacement;
+            let typo: String = typo.into_iter().collect();
+            assert_eq!(Pnr::parse(&typo).unwrap_err(), PnrError::CheckMismatch, "{}", typo);
+        }
+    }
+}
+
+#[test]
+fn rejects_malformed_input() {
+    assert_eq!(Pnr::parse("ABC").unwrap_err(), PnrError::WrongLength(3));
+    assert_eq!(Pnr::parse("ABCDEFGHJU").unwrap_err(), PnrError::InvalidCharacter('U'));
+    assert_eq!(Pnr::parse("").unwrap_err(), PnrError::WrongLength(0));
+}
+
+#[test]
+fn seeded_pnrs_are_stable() {
+    assert_eq!(Pnr::from_seed("trains/t1/u1/0"), Pnr::from_seed("trains/t1/u1/0"));
+    assert_ne!(Pnr::from_seed("trains/t1/u1/0"), Pnr::from_seed("trains/t1/u2/0"));
+}
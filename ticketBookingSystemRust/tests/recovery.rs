@@ -0,0 +1,107 @@
+// Tests for recovery mode: corrupt databases are quarantined, never overwritten, and their readable
+// records are salvaged.
+use std::fs;
+use std::io::ErrorKind;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::SeatLayout;
+use train_booking::entities::user::User;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+
+fn vehicle(id: &str) -> Vehicle {
+    Vehicle {
+        vehicle_id: id.to_string(),
+        name: "Express".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats: SeatLayout::new(SeatClass::ChairCar, 1, 1, 4),
+    }
+}
+
+// A current database whose second vehicle is damaged and whose last vehicle was cut off mid-write.
+fn damaged_database() -> String {
+    format!(
+        r#"{{"schemaVersion": 6, "data": {{
+            "trains": [],
+            "vehicles": [{}, {{"vehicleId": "v2", "name": }}, {}],
+            "users": [{{"userId": "u1", "name": "Asha", "aadharCard": "234123412346"}}, {{"userId": "u2", "na"#,
+        serde_json::to_string(&vehicle("v1")).unwrap(),
+        serde_json::to_string(&vehicle("v3")).unwrap(),
+    )
+}
+
+#[test]
+fn a_corrupt_file_is_never_overwritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, damaged_database()).unwrap();
+    let vehicles = FileIOImpl::<Vehicle>::with_path(&path);
+
+    assert_eq!(vehicles.read_from_file().unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(vehicles.save_to_file(&vehicle("v4")).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(vehicles.delete_from_file("v1").unwrap_err().kind(), ErrorKind::InvalidData);
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), damaged_database());
+}
+
+#[test]
+fn recovery_quarantines_the_file_and_keeps_the_readable_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(&path, damaged_database()).unwrap();
+
+    let report = FileIOImpl::<Vehicle>::with_path(&path).recover().unwrap();
+
+    // v1, v3 and u1 parse; v2 and the truncated u2 don't.
+    assert_eq!((report.salvaged, report.discarded), (3, 2));
+    let quarantined = report.quarantined_to.unwrap();
+    let prefix = format!("{}.corrupt-", path.display());
+    assert!(quarantined.starts_with(&prefix) && quarantined[prefix.len()..].parse::<u64>().is_ok());
+    assert_eq!(fs::read_to_string(&quarantined).unwrap(), damaged_database());
+
+    let vehicles = FileIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap();
+    assert_eq!(vehicles.iter().map(|v| v.vehicle_id.as_str()).collect::<Vec<_>>(), vec!["v1", "v3"]);
+    let users = FileIOImpl::<User>::with_path(&path).read_from_file().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].reveal_aadhar(), "234123412346");
+}
+
+#[test]
+fn salvaged_records_of_the_original_layout_are_upgraded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let legacy = r#"[{"vehicleId": "123", "name": "Express", "source": "A", "destination": "B", "time": 0,
+                      "seats": [[{"userId": "u1", "name": "Asha", "aadharCard": "1111"}]]},
+                     {"vehicleId": "456", "seats": [[{"userId": "u2""#;
+    fs::write(&path, legacy).unwrap();
+
+    let report = FileIOImpl::<Vehicle>::with_path(&path).recover().unwrap();
+
+    // The open record is lost along with the passenger inside it, which never closed either.
+    assert_eq!((report.salvaged, report.discarded), (1, 1));
+    let vehicles = FileIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap();
+    assert_eq!(vehicles.len(), 1);
+    assert_eq!(vehicles[0].seats.seats_of("u1").len(), 1);
+    assert_eq!(FileIOImpl::<User>::with_path(&path).read_from_file().unwrap()[0].reveal_aadhar(), "1111");
+}
+
+#[test]
+fn healthy_and_missing_files_are_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let vehicles = FileIOImpl::<Vehicle>::with_path(&path);
+
+    let report = vehicles.recover().unwrap();
+    assert_eq!((report.quarantined_to, report.salvaged, report.discarded), (None, 0, 0));
+    assert!(!path.exists());
+
+    vehicles.save_to_file(&vehicle("v1")).unwrap();
+    vehicles.save_to_file(&vehicle("v2")).unwrap();
+    let before = fs::read_to_string(&path).unwrap();
+
+    let report = vehicles.recover().unwrap();
+    assert_eq!((report.quarantined_to, report.salvaged, report.discarded), (None, 2, 0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().contains("corrupt")).count(), 0);
+}
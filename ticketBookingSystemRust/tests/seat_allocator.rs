@@ -0,0 +1,193 @@
+// Tests for seat layouts (seat kinds, classes, compositions) and the seat allocation strategies, over
+// partly booked ("fragmented") seat maps.
+use train_booking::entities::pnr::Pnr;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::{Coach, SeatKind, SeatLayout, SeatPosition, SLEEPER_BAY};
+use train_booking::service::seat_allocator::{FillFrontToBack, KeepTogether, SeatAllocator};
+
+// Builds a layout of chair car coaches from a map per coach (C1, C2, ...): one string per row, 'X' for a booked seat and
+// '.' for a free one.
+fn layout(coaches: &[&[&str]]) -> SeatLayout {
+    let coaches = coaches
+        .iter()
+        .enumerate()
+        .map(|(c, rows)| {
+            let mut coach = Coach::new(SeatClass::ChairCar, format!("C{}", c + 1), rows.len(), rows[0].len());
+            for (row, map) in coach.rows.iter_mut().zip(rows.iter()) {
+                for (seat, mark) in row.iter_mut().zip(map.chars()) {
+                    if mark == 'X' {
+                        seat.passenger = Some("booked".to_string());
+                    }
+                }
+            }
+            coach
+        })
+        .collect();
+    SeatLayout { coaches }
+}
+
+// Renders allocated positions as (coach, row, column) triples.
+fn places(positions: &[SeatPosition]) -> Vec<(String, usize, usize)> {
+    positions.iter().map(|p| (p.number.coach.clone(), p.row, p.column)).collect()
+}
+
+fn place(coach: &str, row: usize, column: usize) -> (String, usize, usize) {
+    (coach.to_string(), row, column)
+}
+
+#[test]
+fn front_to_back_takes_the_first_free_seats() {
+    let seats = layout(&[&["X.X.", "..XX"]]);
+
+    let positions = FillFrontToBack.allocate(&seats, 3, None).unwrap();
+
+    assert_eq!(places(&positions), vec![place("C1", 0, 1), place("C1", 0, 3), place("C1", 1, 0)]);
+}
+
+#[test]
+fn keep_together_prefers_adjacent_seats_in_one_row() {
+    let seats = layout(&[&["X.X.X.", "XX...X", "......"]]);
+
+    let positions = KeepTogether.allocate(&seats, 3, None).unwrap();
+
+    assert_eq!(places(&positions), vec![place("C1", 1, 2), place("C1", 1, 3), place("C1", 1, 4)]);
+}
+
+#[test]
+fn keep_together_falls_back_to_one_row() {
+    let seats = layout(&[&["X.X.XX", ".X.X.X"]]);
+
+    let positions = KeepTogether.allocate(&seats, 3, None).unwrap();
+
+    assert_eq!(places(&positions), vec![place("C1", 1, 0), place("C1", 1, 2), place("C1", 1, 4)]);
+}
+
+#[test]
+fn keep_together_falls_back_to_the_fewest_rows_of_one_coach() {
+    let seats = layout(&[
+        &["X.XXXX", "XXXXXX", "XXXXX.", "X.XXXX"],
+        &["XXXX.X", ".XXXXX", "XXXXXX", "XXXXXX"],
+    ]);
+
+    let positions = KeepTogether.allocate(&seats, 2, None).unwrap();
+
+    // C1 rows 2-3 and C2 rows 0-1 both hold two free seats; the front one wins.
+    assert_eq!(places(&positions), vec![place("C1", 2, 5), place("C1", 3, 1)]);
+}
+
+#[test]
+fn keep_together_picks_the_tightest_block() {
+    let seats = layout(&[&["X.XXXX", "XXXXXX", "XXXX.X", "XXXXXX"], &["XXXXX.", "X.XXXX", "XXXXXX", "XXXXXX"]]);
+
+    let positions = KeepTogether.allocate(&seats, 2, None).unwrap();
+
+    assert_eq!(places(&positions), vec![place("C2", 0, 5), place("C2", 1, 1)]);
+}
+
+#[test]
+fn keep_together_spreads_over_coaches_as_a_last_resort() {
+    let seats = layout(&[&["XXX.", "XXXX"], &["X.XX", "XXXX"]]);
+
+    let positions = KeepTogether.allocate(&seats, 2, None).unwrap();
+
+    assert_eq!(places(&positions), vec![place("C1", 0, 3), place("C2", 0, 1)]);
+}
+
+#[test]
+fn allocators_refuse_when_too_few_seats_are_free() {
+    let seats = layout(&[&["X.X", "XXX"]]);
+
+    assert!(FillFrontToBack.allocate(&seats, 2, None).is_none());
+    assert!(KeepTogether.allocate(&seats, 2, None).is_none());
+    assert_eq!(KeepTogether.allocate(&seats, 1, None).unwrap().len(), 1);
+}
+
+#[test]
+fn allocated_seats_can_be_assigned() {
+    let mut seats = layout(&[&["X...", "...."]]);
+    let pnr = Pnr::generate(|_| false);
+
+    let positions = KeepTogether.allocate(&seats, 3, None).unwrap();
+    let numbers = seats.assign_at(&positions, &["u1", "u2", "u3"], &pnr).unwrap();
+
+    assert_eq!(numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>(), vec!["C1-2", "C1-3", "C1-4"]);
+    // Positions that are no longer free are refused as a whole.
+    assert!(seats.assign_at(&positions[..1], &["u4"], &pnr).is_none());
+    assert_eq!(seats.available(), 4);
+}
+
+#[test]
+fn preferences_pick_seats_of_that_kind() {
+    let seats = layout(&[&["X.....", "......"]]);
+
+    // Row 0 has one free window seat and row 1 two, so the pair goes to row 1.
+    let positions = KeepTogether.allocate(&seats, 2, Some(SeatKind::Window)).unwrap();
+    assert_eq!(places(&positions), vec![place("C1", 1, 0), place("C1", 1, 5)]);
+
+    let positions = FillFrontToBack.allocate(&seats, 3, Some(SeatKind::Aisle)).unwrap();
+    assert_eq!(places(&positions), vec![place("C1", 0, 2), place("C1", 0, 3), place("C1", 1, 2)]);
+}
+
+#[test]
+fn preferences_give_way_when_too_few_seats_of_that_kind_are_free() {
+    let seats = layout(&[&["X....X", "X.X..X"]]);
+
+    // No window seat is free, so the pair is seated together anyway.
+    let positions = KeepTogether.allocate(&seats, 2, Some(SeatKind::Window)).unwrap();
+    assert_eq!(places(&positions), vec![place("C1", 0, 1), place("C1", 0, 2)]);
+
+    // Front to back still hands out the seats of the preferred kind that are left first.
+    let positions = FillFrontToBack.allocate(&seats, 5, Some(SeatKind::Middle)).unwrap();
+    assert_eq!(
+        places(&positions),
+        vec![place("C1", 0, 1), place("C1", 0, 4), place("C1", 1, 1), place("C1", 1, 4), place("C1", 0, 2)]
+    );
+}
+
+#[test]
+fn rows_and_sleeper_bays_have_seat_kinds() {
+    let kinds = |coach: &Coach| coach.rows[0].iter().map(|s| s.kind.unwrap().to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        kinds(&Coach::new(SeatClass::ChairCar, "C1".to_string(), 1, 6)),
+        ["window seat", "middle seat", "aisle seat", "aisle seat", "middle seat", "window seat"]
+    );
+    // Berth classes get berths, even in coaches of another size.
+    let odd = Coach::new(SeatClass::Sleeper, "S9".to_string(), 2, 6);
+    assert_eq!(kinds(&odd)[..3], ["lower berth", "middle berth", "upper berth"]);
+    assert_eq!(odd.rows[1][0].kind, Some(SeatKind::SideLower));
+    assert!(odd.seats().all(|s| !matches!(s.kind, Some(SeatKind::Window | SeatKind::Middle | SeatKind::Aisle))));
+
+    let sleeper = Coach::with_berths(SeatClass::Sleeper, "S1".to_string(), 2, &SLEEPER_BAY);
+    assert_eq!(sleeper.rows[1][0].number, 9);
+    assert_eq!(kinds(&sleeper)[6..], ["side lower berth", "side upper berth"]);
+
+    let seats = SeatLayout { coaches: vec![sleeper] };
+    let positions = KeepTogether.allocate(&seats, 2, Some(SeatKind::LowerBerth)).unwrap();
+    assert_eq!(places(&positions), vec![place("S1", 0, 0), place("S1", 0, 3)]);
+}
+
+#[test]
+fn seat_kinds_parse_from_user_input() {
+    assert_eq!(SeatKind::parse("Window"), Some(SeatKind::Window));
+    assert_eq!(SeatKind::parse("middle"), Some(SeatKind::Middle));
+    assert_eq!(SeatKind::parse("middle berth"), Some(SeatKind::MiddleBerth));
+    assert_eq!(SeatKind::parse("lower"), Some(SeatKind::LowerBerth));
+    assert_eq!(SeatKind::parse("side-upper berth"), Some(SeatKind::SideUpper));
+    assert_eq!(SeatKind::parse("window berth"), None);
+    assert_eq!(SeatKind::parse("sunroof"), None);
+}
+
+#[test]
+fn compositions_number_coaches_per_class() {
+    let seats = SeatLayout::from_composition(&[(SeatClass::Sleeper, 2), (SeatClass::ThirdAc, 1), (SeatClass::ChairCar, 1)]);
+
+    let codes: Vec<&str> = seats.coaches.iter().map(|c| c.code.as_str()).collect();
+    assert_eq!(codes, vec!["S1", "S2", "B1", "C1"]);
+    assert_eq!(seats.classes(), vec![SeatClass::Sleeper, SeatClass::ThirdAc, SeatClass::ChairCar]);
+    assert_eq!(seats.of_class(SeatClass::Sleeper).capacity(), 144);
+    assert_eq!(seats.of_class(SeatClass::ChairCar).coaches[0].rows[0].len(), 5);
+    assert_eq!(SeatClass::parse("3a"), Some(SeatClass::ThirdAc));
+    assert_eq!(SeatClass::parse("chair car"), Some(SeatClass::ChairCar));
+    assert_eq!(SeatClass::parse("4A"), None);
+}
@@ -0,0 +1,270 @@
+// Tests for the SQLite backend: round trips, single-record reads and updates, upgrades of older
+// databases and the db.json import.
+use std::path::Path;
+use std::time::Duration;
+use rusqlite::Connection;
+use serde_json::Value;
+use train_booking::entities::aadhar::AadharNumber;
+use train_booking::entities::pnr::Pnr;
+use train_booking::entities::seat_class::SeatClass;
+use train_booking::entities::seat_layout::{SeatKind, SeatLayout};
+use train_booking::entities::train::Train;
+use train_booking::entities::user::User;
+use train_booking::entities::vehicle::Vehicle;
+use train_booking::service::file_io::{FileIO, FileIOImpl};
+use train_booking::service::seat_allocator::{FillFrontToBack, SeatAllocator};
+use train_booking::service::sqlite_io::{self, SqliteIOImpl, SQLITE_SCHEMA_VERSION};
+
+// The tables as created at schema version 1, before PNRs, seat kinds and classes were stored.
+const SCHEMA_V1: &str = "
+    CREATE TABLE users (user_id TEXT PRIMARY KEY, name TEXT NOT NULL, aadhar_card TEXT NOT NULL);
+    CREATE TABLE trains (id TEXT PRIMARY KEY, name TEXT NOT NULL, source TEXT NOT NULL, destination TEXT NOT NULL, time INTEGER NOT NULL);
+    CREATE TABLE vehicles (id TEXT PRIMARY KEY, name TEXT NOT NULL, source TEXT NOT NULL, destination TEXT NOT NULL, time INTEGER NOT NULL);
+    CREATE TABLE seats (
+        collection TEXT NOT NULL, entity_id TEXT NOT NULL, coach_index INTEGER NOT NULL, coach TEXT NOT NULL,
+        row_index INTEGER NOT NULL, column_index INTEGER NOT NULL, number INTEGER NOT NULL,
+        user_id TEXT REFERENCES users(user_id),
+        PRIMARY KEY (collection, entity_id, coach_index, row_index, column_index)
+    );
+    CREATE INDEX seats_by_user ON seats(user_id);
+    PRAGMA user_version = 1;
+";
+
+fn user(id: &str) -> User {
+    User { user_id: id.to_string(), name: format!("Name {}", id), aadhar_card: AadharNumber::parse("234123412346").unwrap() }
+}
+
+// Seats `user_ids` under one new PNR where a front-to-back booking would put them.
+fn occupy(seats: &mut SeatLayout, user_ids: &[&str]) {
+    let positions = FillFrontToBack.allocate(seats, user_ids.len(), None).unwrap();
+    seats.assign_at(&positions, user_ids, &Pnr::generate(|_| false)).unwrap();
+}
+
+// A vehicle with one chair car coach of 2 rows of 4 seats, the first two held by `user_id`.
+fn vehicle(id: &str, user_id: &str) -> Vehicle {
+    let mut seats = SeatLayout::new(SeatClass::ChairCar, 1, 2, 4);
+    occupy(&mut seats, &[user_id, user_id]);
+    Vehicle { vehicle_id: id.to_string(), name: "Express".to_string(), source: "A".to_string(), destination: "B".to_string(), time: 0, seats }
+}
+
+fn train(id: &str) -> Train {
+    Train {
+        train_id: id.to_string(),
+        name: "Rajdhani".to_string(),
+        source: "A".to_string(),
+        destination: "B".to_string(),
+        time: 0,
+        seats: SeatLayout::default(),
+    }
+}
+
+fn json(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).unwrap()
+}
+
+fn user_version(path: &Path) -> i64 {
+    Connection::open(path).unwrap().pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn entities_round_trip_through_the_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let users = SqliteIOImpl::<User>::with_path(&path);
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(&path);
+    let trains = SqliteIOImpl::<Train>::with_path(&path);
+    users.save_to_file(&user("u1")).unwrap();
+    vehicles.save_to_file(&vehicle("v1", "u1")).unwrap();
+    vehicles.save_to_file(&vehicle("v2", "u1")).unwrap();
+    trains.save_to_file(&train("t1")).unwrap();
+
+    assert_eq!(json(&users.read_from_file().unwrap()), json(&vec![user("u1")]));
+    let stored = vehicles.read_from_file().unwrap();
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].seats.seats_of("u1").len(), 2);
+    assert_eq!(stored[0].seats.coaches[0].rows[0][0].kind, Some(SeatKind::Window));
+    assert_eq!(json(&trains.read_from_file().unwrap()), json(&vec![train("t1")]));
+
+    assert!(vehicles.delete_from_file("v1").unwrap());
+    assert!(!vehicles.delete_from_file("v1").unwrap());
+    assert_eq!(vehicles.read_from_file().unwrap()[0].vehicle_id, "v2");
+    assert_eq!(user_version(&path), SQLITE_SCHEMA_VERSION);
+}
+
+#[test]
+fn saving_an_entity_again_replaces_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(dir.path().join("db.sqlite3"));
+    SqliteIOImpl::<User>::with_path(vehicles.path()).save_to_file(&user("u1")).unwrap();
+    let mut v1 = vehicle("v1", "u1");
+    vehicles.save_to_file(&v1).unwrap();
+
+    v1.seats.release("u1");
+    v1.name = "Renamed".to_string();
+    vehicles.save_to_file(&v1).unwrap();
+
+    assert_eq!(json(&vehicles.read_from_file().unwrap()), json(&vec![v1]));
+}
+
+// Returns the rowid of every seat row, which changes if a row is deleted and inserted again.
+fn seat_rowids(path: &Path) -> Vec<(String, i64)> {
+    let conn = Connection::open(path).unwrap();
+    let mut stmt = conn.prepare("SELECT entity_id, rowid FROM seats ORDER BY rowid").unwrap();
+    stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn records_are_read_and_updated_one_at_a_time() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(&path);
+    let users = SqliteIOImpl::<User>::with_path(&path);
+    users.save_to_file(&user("u1")).unwrap();
+    users.save_to_file(&user("u2")).unwrap();
+    vehicles.save_to_file(&vehicle("v1", "u1")).unwrap();
+    vehicles.save_to_file(&vehicle("v2", "u1")).unwrap();
+    let v2_pnr = vehicles.read_record("v2").unwrap().unwrap().seats.coaches[0].rows[0][0].pnr.clone().unwrap();
+
+    assert_eq!(vehicles.read_record("v2").unwrap().unwrap().vehicle_id, "v2");
+    assert!(vehicles.read_record("v3").unwrap().is_none());
+    assert_eq!(vehicles.read_record_with_pnr(&v2_pnr).unwrap().unwrap().vehicle_id, "v2");
+    assert!(vehicles.read_record_with_pnr(&Pnr::generate(|_| false)).unwrap().is_none());
+    assert_eq!(json(&users.read_record("u2").unwrap()), json(&Some(user("u2"))));
+
+    let rowids = seat_rowids(&path);
+    vehicles
+        .update_record("v1", &mut |v1, taken| {
+            assert!(taken(&v2_pnr));
+            occupy(&mut v1.as_mut().unwrap().seats, &["u2"]);
+            Ok(())
+        })
+        .unwrap();
+
+    // The booking updated its seat row in place instead of rewriting the layout.
+    assert_eq!(seat_rowids(&path), rowids);
+    let stored = vehicles.read_from_file().unwrap();
+    assert_eq!(stored[0].seats.seats_of("u2").len(), 1);
+    assert_eq!(json(&stored[1]), json(&vehicles.read_record("v2").unwrap().unwrap()));
+
+    // A new record is inserted and a cleared one deleted.
+    vehicles
+        .update_record("v3", &mut |v3, _| {
+            *v3 = Some(vehicle("v3", "u2"));
+            Ok(())
+        })
+        .unwrap();
+    vehicles
+        .update_record("v1", &mut |v1, _| {
+            *v1 = None;
+            Ok(())
+        })
+        .unwrap();
+    let ids: Vec<String> = vehicles.read_from_file().unwrap().into_iter().map(|v| v.vehicle_id).collect();
+    assert_eq!(ids, vec!["v2", "v3"]);
+}
+
+#[test]
+fn changing_the_layout_rewrites_the_seats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(&path);
+    SqliteIOImpl::<User>::with_path(&path).save_to_file(&user("u1")).unwrap();
+    vehicles.save_to_file(&vehicle("v1", "u1")).unwrap();
+
+    let mut v1 = vehicle("v1", "u1");
+    v1.seats = SeatLayout::new(SeatClass::Sleeper, 2, 1, 3);
+    vehicles
+        .update_in_file(&mut |stored| {
+            stored[0] = v1.clone();
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(json(&vehicles.read_from_file().unwrap()), json(&vec![v1]));
+    assert_eq!(seat_rowids(&path).len(), 6);
+}
+
+#[test]
+fn reads_do_not_wait_for_a_writer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(&path).with_busy_timeout(Duration::from_millis(50));
+    SqliteIOImpl::<User>::with_path(&path).save_to_file(&user("u1")).unwrap();
+    vehicles.save_to_file(&vehicle("v1", "u1")).unwrap();
+    let writer = Connection::open(&path).unwrap();
+    writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+
+    assert_eq!(vehicles.read_from_file().unwrap().len(), 1);
+    assert!(vehicles.read_record("v1").unwrap().is_some());
+    let err = vehicles.save_to_file(&vehicle("v1", "u1")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[test]
+fn version_1_databases_are_upgraded_step_by_step() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(SCHEMA_V1).unwrap();
+    conn.execute("INSERT INTO users VALUES ('u1', 'Asha', '234123412346')", []).unwrap();
+    conn.execute("INSERT INTO vehicles VALUES ('v1', 'Express', 'A', 'B', 0)", []).unwrap();
+    for (row, column, number, user_id) in [(0, 0, 1, Some("u1")), (0, 1, 2, Some("u1")), (0, 2, 3, None), (0, 3, 4, None), (1, 0, 5, None)] {
+        conn.execute(
+            "INSERT INTO seats VALUES ('vehicles', 'v1', 0, 'S1', ?1, ?2, ?3, ?4)",
+            rusqlite::params![row, column, number, user_id],
+        )
+        .unwrap();
+    }
+    drop(conn);
+
+    let vehicles = SqliteIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap();
+
+    assert_eq!(user_version(&path), SQLITE_SCHEMA_VERSION);
+    let coach = &vehicles[0].seats.coaches[0];
+    // v2: u1's seats were one booking and share one PNR.
+    let pnr = coach.rows[0][0].pnr.clone().unwrap();
+    assert!(Pnr::parse(&pnr.to_string()).is_ok());
+    assert_eq!(coach.rows[0][1].pnr, Some(pnr));
+    assert_eq!(coach.rows[0][2].pnr, None);
+    // v3: kinds follow from each seat's place in its row.
+    let kinds: Vec<Option<SeatKind>> = coach.rows[0].iter().map(|s| s.kind).collect();
+    assert_eq!(kinds, vec![Some(SeatKind::Window), Some(SeatKind::Aisle), Some(SeatKind::Aisle), Some(SeatKind::Window)]);
+    assert_eq!(coach.rows[1][0].kind, Some(SeatKind::Window));
+    // v4: existing coaches, rows of seats, are sold as Chair Car.
+    assert_eq!(coach.class, SeatClass::ChairCar);
+}
+
+#[test]
+fn newer_databases_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite3");
+    Connection::open(&path).unwrap().pragma_update(None, "user_version", SQLITE_SCHEMA_VERSION + 1).unwrap();
+
+    let error = SqliteIOImpl::<Vehicle>::with_path(&path).read_from_file().unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn import_json_copies_every_collection_and_can_be_rerun() {
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("db.json");
+    FileIOImpl::<User>::with_path(&json_path).save_to_file(&user("u1")).unwrap();
+    FileIOImpl::<Vehicle>::with_path(&json_path).save_to_file(&vehicle("v1", "u1")).unwrap();
+    FileIOImpl::<Train>::with_path(&json_path).save_to_file(&train("t1")).unwrap();
+    let sqlite = SqliteIOImpl::<Vehicle>::with_path(dir.path().join("db.sqlite3"));
+    assert!(!sqlite_io::has_entities(&sqlite).unwrap());
+
+    let report = sqlite_io::import_json(&json_path, &sqlite).unwrap();
+
+    assert_eq!((report.users, report.trains, report.vehicles), (1, 1, 1));
+    assert!(sqlite_io::has_entities(&sqlite).unwrap());
+    let json_vehicles = FileIOImpl::<Vehicle>::with_path(&json_path).read_from_file().unwrap();
+    assert_eq!(json(&sqlite.read_from_file().unwrap()), json(&json_vehicles));
+    let trains = SqliteIOImpl::<Train>::with_path(sqlite.path()).read_from_file().unwrap();
+    assert_eq!(json(&trains), json(&vec![train("t1")]));
+
+    sqlite_io::import_json(&json_path, &sqlite).unwrap();
+    assert_eq!(sqlite.read_from_file().unwrap().len(), 1);
+    assert_eq!(SqliteIOImpl::<User>::with_path(sqlite.path()).read_from_file().unwrap().len(), 1);
+}